@@ -0,0 +1,256 @@
+//! `#[derive(ConstructTable)]`, which implements `Serialize` and `Deserialize` for a struct stored as a
+//! Hash Table, with `#[cstc(...)]` field attributes choosing how each field is stored:
+//!
+//! - `key = "..."` sets the key the field is stored under, which defaults to the field name.
+//! - `int` and `float` set the type tag of a numeric field, converting its value as needed.
+//!   Reading the field only accepts values with that tag.
+//! - `construct_bool` stores a `bool` field as a number, an int unless `float` is also given.
+//!
+//! `int`, `float` and `construct_bool` convert the field's own value, so they can't be used on `Option` fields.
+//! `Option` fields that are `None` aren't written, and read as `None` when their key is missing.
+//! Entries with unknown keys are skipped, and of entries with the same key the first is used.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Ident, LitStr, Type};
+
+#[proc_macro_derive(ConstructTable, attributes(cstc))]
+pub fn derive_construct_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tag {
+    Int,
+    Float,
+}
+
+struct Field<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    key: String,
+    tag: Option<Tag>,
+    construct_bool: bool,
+}
+
+impl Field<'_> {
+    fn is_option(&self) -> bool {
+        is_option(self.ty)
+    }
+
+    /// The type the field is (de)serialized through.
+    fn wrapper(&self) -> Option<TokenStream2> {
+        let ty = if self.construct_bool { quote!(bool) } else { let ty = self.ty; quote!(#ty) };
+        match self.tag {
+            Some(Tag::Int) => Some(quote!(::serde_construct_classic::__private::Int::<#ty>)),
+            Some(Tag::Float) => Some(quote!(::serde_construct_classic::__private::Float::<#ty>)),
+            None => None,
+        }
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.segments.last().is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Field<'_>> {
+    let ident = field.ident.as_ref().expect("named fields have names");
+    let mut key = None;
+    let mut tag = None;
+    let mut construct_bool = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("cstc")) {
+        attr.parse_nested_meta(|meta| {
+            let converts = ["int", "float", "construct_bool"].iter().any(|name| meta.path.is_ident(name));
+            if converts && is_option(&field.ty) {
+                return Err(meta.error("`int`, `float` and `construct_bool` convert the field's own value and can't be used on an `Option` field"));
+            }
+            let mut set_tag = |new| {
+                if tag.is_some_and(|tag| tag != new) {
+                    return Err(meta.error("a field can't be both `int` and `float`"));
+                }
+                tag = Some(new);
+                Ok(())
+            };
+            if meta.path.is_ident("int") {
+                set_tag(Tag::Int)
+            } else if meta.path.is_ident("float") {
+                set_tag(Tag::Float)
+            } else if meta.path.is_ident("construct_bool") {
+                construct_bool = true;
+                Ok(())
+            } else if meta.path.is_ident("key") {
+                key = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `int`, `float`, `construct_bool` or `key = \"...\"`"))
+            }
+        })?;
+    }
+    if construct_bool && tag.is_none() {
+        tag = Some(Tag::Int);
+    }
+    Ok(Field {
+        ident,
+        ty: &field.ty,
+        key: key.unwrap_or_else(|| ident.to_string()),
+        tag,
+        construct_bool,
+    })
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input.ident, "ConstructTable can only be derived for structs"));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(&input.ident, "ConstructTable can only be derived for structs with named fields"));
+    };
+    if let Some(lifetime) = input.generics.lifetimes().next() {
+        return Err(Error::new_spanned(lifetime, "ConstructTable can't be derived for structs with lifetime parameters"));
+    }
+    let fields = named.named.iter().map(parse_field).collect::<syn::Result<Vec<_>>>()?;
+    let serialize = expand_serialize(input, &fields);
+    let deserialize = expand_deserialize(input, &fields);
+    Ok(quote! {
+        #serialize
+        #deserialize
+    })
+}
+
+fn expand_serialize(input: &DeriveInput, fields: &[Field]) -> TokenStream2 {
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::serde_construct_classic::__private::serde::Serialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let name_str = name.to_string();
+    let len = fields.len();
+    // the key count comes first in the table, so it has to leave out the fields that get skipped
+    let skipped = fields.iter().filter(|field| field.is_option()).map(|field| {
+        let ident = field.ident;
+        quote!(- usize::from(::core::option::Option::is_none(&self.#ident)))
+    });
+    let entries = fields.iter().map(|field| {
+        let ident = field.ident;
+        let key = &field.key;
+        let value = match field.wrapper() {
+            Some(wrapper) => quote!(&#wrapper(self.#ident)),
+            None => quote!(&self.#ident),
+        };
+        let entry = quote! {
+            ::serde_construct_classic::__private::serde::ser::SerializeStruct::serialize_field(&mut __table, #key, #value)?;
+        };
+        if field.is_option() {
+            quote!(if ::core::option::Option::is_some(&self.#ident) { #entry })
+        } else {
+            entry
+        }
+    });
+    quote! {
+        impl #impl_generics ::serde_construct_classic::__private::serde::Serialize for #name #ty_generics #where_clause {
+            fn serialize<__S>(&self, __serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error>
+            where
+                __S: ::serde_construct_classic::__private::serde::Serializer,
+            {
+                let __len = #len #(#skipped)*;
+                let mut __table = ::serde_construct_classic::__private::serde::Serializer::serialize_struct(__serializer, #name_str, __len)?;
+                #(#entries)*
+                ::serde_construct_classic::__private::serde::ser::SerializeStruct::end(__table)
+            }
+        }
+    }
+}
+
+fn expand_deserialize(input: &DeriveInput, fields: &[Field]) -> TokenStream2 {
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::serde_construct_classic::__private::serde::Deserialize<'de>));
+    }
+    generics.params.insert(0, GenericParam::Lifetime(parse_quote!('de)));
+    let (impl_generics, visitor_generics, where_clause) = generics.split_for_impl();
+    let name_str = name.to_string();
+    let expecting = format!("struct {name}");
+
+    let slots: Vec<Ident> = (0..fields.len()).map(|i| format_ident!("__field{}", i)).collect();
+    let keys: Vec<&str> = fields.iter().map(|field| field.key.as_str()).collect();
+    let declarations = fields.iter().zip(&slots).map(|(field, slot)| {
+        let ty = field.ty;
+        quote!(let mut #slot: ::core::option::Option<#ty> = ::core::option::Option::None;)
+    });
+    let arms = fields.iter().zip(&slots).map(|(field, slot)| {
+        let key = &field.key;
+        let value = match field.wrapper() {
+            Some(wrapper) => quote!(::serde_construct_classic::__private::serde::de::MapAccess::next_value::<#wrapper>(&mut __map)?.0),
+            None => quote!(::serde_construct_classic::__private::serde::de::MapAccess::next_value(&mut __map)?),
+        };
+        quote!(#key if #slot.is_none() => #slot = ::core::option::Option::Some(#value),)
+    });
+    let unwraps = fields.iter().zip(&slots).map(|(field, slot)| {
+        let key = &field.key;
+        let missing = match field.wrapper() {
+            Some(wrapper) => quote!(::serde_construct_classic::__private::missing_field::<#wrapper, __A::Error>(#key)?.0),
+            None => quote!(::serde_construct_classic::__private::missing_field(#key)?),
+        };
+        quote! {
+            let #slot = match #slot {
+                ::core::option::Option::Some(value) => value,
+                ::core::option::Option::None => #missing,
+            };
+        }
+    });
+    let idents = fields.iter().map(|field| field.ident);
+    let visitor = Ident::new("__Visitor", Span::call_site());
+
+    quote! {
+        impl #impl_generics ::serde_construct_classic::__private::serde::Deserialize<'de> for #name #ty_generics #where_clause {
+            fn deserialize<__D>(__deserializer: __D) -> ::core::result::Result<Self, __D::Error>
+            where
+                __D: ::serde_construct_classic::__private::serde::Deserializer<'de>,
+            {
+                struct #visitor #impl_generics #where_clause {
+                    marker: ::core::marker::PhantomData<(#name #ty_generics, &'de ())>,
+                }
+
+                impl #impl_generics ::serde_construct_classic::__private::serde::de::Visitor<'de> for #visitor #visitor_generics #where_clause {
+                    type Value = #name #ty_generics;
+
+                    fn expecting(&self, __f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        __f.write_str(#expecting)
+                    }
+
+                    fn visit_map<__A>(self, mut __map: __A) -> ::core::result::Result<Self::Value, __A::Error>
+                    where
+                        __A: ::serde_construct_classic::__private::serde::de::MapAccess<'de>,
+                    {
+                        #(#declarations)*
+                        while let ::core::option::Option::Some(__key) = ::serde_construct_classic::__private::serde::de::MapAccess::next_key::<::serde_construct_classic::__private::Key<'de>>(&mut __map)? {
+                            match &*__key.0 {
+                                #(#arms)*
+                                _ => {
+                                    ::serde_construct_classic::__private::serde::de::MapAccess::next_value::<::serde_construct_classic::__private::serde::de::IgnoredAny>(&mut __map)?;
+                                },
+                            }
+                        }
+                        #(#unwraps)*
+                        ::core::result::Result::Ok(#name { #(#idents: #slots),* })
+                    }
+                }
+
+                ::serde_construct_classic::__private::serde::Deserializer::deserialize_struct(
+                    __deserializer,
+                    #name_str,
+                    &[#(#keys),*],
+                    #visitor { marker: ::core::marker::PhantomData },
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+fn main() {
+    // The header is committed; regenerating it is opt-in so building doesn't need cbindgen or
+    // write into the source tree.
+    #[cfg(feature = "regenerate-header")]
+    regenerate_header();
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+#[cfg(feature = "regenerate-header")]
+fn regenerate_header() {
+    use std::env;
+    use std::path::PathBuf;
+
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("could not generate the C header")
+        .write_to_file(crate_dir.join("include/cstc.h"));
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
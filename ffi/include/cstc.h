@@ -0,0 +1,245 @@
+#ifndef CSTC_H
+#define CSTC_H
+
+/* Generated by cbindgen from ffi/src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum CstcErrorCode {
+  CSTC_ERROR_CODE_OK = 0,
+  CSTC_ERROR_CODE_NULL_POINTER,
+  // A key or string argument is not valid UTF-8.
+  CSTC_ERROR_CODE_INVALID_UTF8,
+  CSTC_ERROR_CODE_NOT_FOUND,
+  CSTC_ERROR_CODE_TYPE_MISMATCH,
+  CSTC_ERROR_CODE_MESSAGE,
+  CSTC_ERROR_CODE_MISSING_STRING_TERMINATOR,
+  CSTC_ERROR_CODE_STRING_LENGTH_ERROR,
+  CSTC_ERROR_CODE_UNKNOWN_TYPE_ID,
+  CSTC_ERROR_CODE_INVALID_PAYLOAD,
+  CSTC_ERROR_CODE_NUMERIC_OVERFLOW,
+  CSTC_ERROR_CODE_TRAILING_CHARACTERS,
+  CSTC_ERROR_CODE_UNEXPECTED_END,
+  CSTC_ERROR_CODE_LENGTH_NOT_GIVEN,
+  CSTC_ERROR_CODE_INVALID_KEY_TYPE,
+  CSTC_ERROR_CODE_NON_NUMERIC_KEY,
+  CSTC_ERROR_CODE_TEXT_ENCODING_ERROR,
+  CSTC_ERROR_CODE_INVALID_HEADER,
+  CSTC_ERROR_CODE_UNSUPPORTED_VALUE,
+  CSTC_ERROR_CODE_IO,
+  CSTC_ERROR_CODE_LIMIT_EXCEEDED,
+} CstcErrorCode;
+
+typedef enum CstcValueKind {
+  CSTC_VALUE_KIND_INT,
+  CSTC_VALUE_KIND_FLOAT,
+  CSTC_VALUE_KIND_STRING,
+  // A value with a type tag other than integer, float or string.
+  CSTC_VALUE_KIND_RAW,
+  // A table stored in place of a value, which `cstc_table_parse` can read.
+  CSTC_VALUE_KIND_TABLE,
+} CstcValueKind;
+
+// A snapshot of a table's entries, in file order.
+typedef struct CstcEntries CstcEntries;
+
+// A parsed Hash Table.
+typedef struct CstcTable CstcTable;
+
+// Details of a failed call.
+typedef struct CstcError {
+  enum CstcErrorCode code;
+  // Offset into the parsed buffer the error was found at, or -1 if not known.
+  int64_t offset;
+  // NUL-terminated UTF-8 description, or null. Owned by the error.
+  char *message;
+} CstcError;
+
+// A borrowed view of a value.
+typedef struct CstcValue {
+  enum CstcValueKind kind;
+  int64_t int_value;
+  double float_value;
+  // NUL-terminated UTF-8 text for strings, the payload of raw values, or the bytes of nested tables.
+  const uint8_t *data;
+  // Length of `data`, not counting the NUL terminator of strings.
+  size_t len;
+  // The type tag of raw values.
+  uint32_t type_id;
+} CstcValue;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an empty table.
+struct CstcTable *cstc_table_new(void);
+
+// Parses a Hash Table from `len` bytes at `data`, storing the new table in `out_table`.
+// Values with unknown type tags are kept as raw values.
+//
+// # Safety
+// `data` must point to `len` readable bytes, `out_table` must be valid for writes,
+// and `error` must be null or a zero-initialized error or one filled in by this library.
+enum CstcErrorCode cstc_table_parse(const uint8_t *data,
+                                    size_t len,
+                                    struct CstcTable **out_table,
+                                    struct CstcError *error);
+
+// Frees a table. Does nothing if `table` is null.
+//
+// # Safety
+// `table` must be null or a table that hasn't been freed yet.
+void cstc_table_free(struct CstcTable *table);
+
+// The number of entries, including duplicate keys.
+//
+// # Safety
+// `table` must be a valid table.
+size_t cstc_table_len(const struct CstcTable *table);
+
+// Whether the table has an entry with `key`.
+//
+// # Safety
+// `table` must be a valid table and `key` a NUL-terminated string.
+bool cstc_table_contains(const struct CstcTable *table, const char *key);
+
+// Looks up the first entry with `key`. Pointers in `out_value` stay valid until the next
+// `cstc_table_get*` call on the table, or until it is edited or freed.
+//
+// # Safety
+// `table` must be a valid table, `key` a NUL-terminated string, `out_value` valid for writes,
+// and `error` null or a zero-initialized error or one filled in by this library.
+enum CstcErrorCode cstc_table_get(struct CstcTable *table,
+                                  const char *key,
+                                  struct CstcValue *out_value,
+                                  struct CstcError *error);
+
+// Gets the integer value of `key`, failing with `TYPE_MISMATCH` for other types.
+//
+// # Safety
+// As for `cstc_table_get`.
+enum CstcErrorCode cstc_table_get_int(struct CstcTable *table,
+                                      const char *key,
+                                      int64_t *out_value,
+                                      struct CstcError *error);
+
+// Gets the float value of `key`, failing with `TYPE_MISMATCH` for other types.
+//
+// # Safety
+// As for `cstc_table_get`.
+enum CstcErrorCode cstc_table_get_float(struct CstcTable *table,
+                                        const char *key,
+                                        double *out_value,
+                                        struct CstcError *error);
+
+// Gets the string value of `key` as NUL-terminated UTF-8, failing with `TYPE_MISMATCH` for other types.
+// The string stays valid for as long as a value from `cstc_table_get` would.
+//
+// # Safety
+// As for `cstc_table_get`.
+enum CstcErrorCode cstc_table_get_string(struct CstcTable *table,
+                                         const char *key,
+                                         const char **out_value,
+                                         struct CstcError *error);
+
+// Sets `key` to an integer, replacing the first entry with that key or appending a new one.
+//
+// # Safety
+// `table` must be a valid table, `key` a NUL-terminated string, and `error` null or a zero-initialized
+// error or one filled in by this library.
+enum CstcErrorCode cstc_table_set_int(struct CstcTable *table,
+                                      const char *key,
+                                      int64_t value,
+                                      struct CstcError *error);
+
+// Sets `key` to a float, like `cstc_table_set_int`.
+//
+// # Safety
+// As for `cstc_table_set_int`.
+enum CstcErrorCode cstc_table_set_float(struct CstcTable *table,
+                                        const char *key,
+                                        double value,
+                                        struct CstcError *error);
+
+// Sets `key` to a NUL-terminated UTF-8 string, like `cstc_table_set_int`.
+// Fails with `TEXT_ENCODING_ERROR` if the string can't be stored as windows-1252.
+//
+// # Safety
+// As for `cstc_table_set_int`, and `value` must be a NUL-terminated string.
+enum CstcErrorCode cstc_table_set_string(struct CstcTable *table,
+                                         const char *key,
+                                         const char *value,
+                                         struct CstcError *error);
+
+// Sets `key` to a value with type tag `type_id` and a `len`-byte payload, like `cstc_table_set_int`.
+//
+// # Safety
+// As for `cstc_table_set_int`, and `payload` must point to `len` readable bytes.
+enum CstcErrorCode cstc_table_set_raw(struct CstcTable *table,
+                                      const char *key,
+                                      uint32_t type_id,
+                                      const uint8_t *payload,
+                                      size_t len,
+                                      struct CstcError *error);
+
+// Removes the first entry with `key`, returning whether there was one.
+//
+// # Safety
+// `table` must be a valid table and `key` a NUL-terminated string.
+bool cstc_table_remove(struct CstcTable *table, const char *key);
+
+// Serializes the table into a new buffer, which must be released with `cstc_bytes_free`.
+// Entries that weren't edited keep their original bytes.
+//
+// # Safety
+// `table` must be a valid table, `out_data` and `out_len` valid for writes,
+// and `error` null or a zero-initialized error or one filled in by this library.
+enum CstcErrorCode cstc_table_to_bytes(const struct CstcTable *table,
+                                       uint8_t **out_data,
+                                       size_t *out_len,
+                                       struct CstcError *error);
+
+// Frees a buffer returned by `cstc_table_to_bytes`. Does nothing if `data` is null.
+//
+// # Safety
+// `data` and `len` must be exactly as returned by `cstc_table_to_bytes`, and not freed yet.
+void cstc_bytes_free(uint8_t *data, size_t len);
+
+// Starts iterating over the table's entries. The iterator holds a copy of the entries,
+// so the table may be edited or freed while iterating.
+//
+// # Safety
+// `table` must be a valid table.
+struct CstcEntries *cstc_entries_new(const struct CstcTable *table);
+
+// Advances to the next entry, returning false once there are none left. `out_key` and the
+// pointers in `out_value` stay valid until the iterator is freed.
+//
+// # Safety
+// `entries` must be a valid iterator, and `out_key` and `out_value` valid for writes.
+bool cstc_entries_next(struct CstcEntries *entries,
+                       const char **out_key,
+                       struct CstcValue *out_value);
+
+// Frees an entry iterator. Does nothing if `entries` is null.
+//
+// # Safety
+// `entries` must be null or an iterator that hasn't been freed yet.
+void cstc_entries_free(struct CstcEntries *entries);
+
+// Frees the message of an error filled in by a failed call, and resets the error.
+//
+// # Safety
+// `error` must be null or point to an error that is zero-initialized or was filled in by this library.
+void cstc_error_free(struct CstcError *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CSTC_H */
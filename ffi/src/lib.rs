@@ -0,0 +1,586 @@
+//! C ABI over `serde_construct_classic::Document`.
+//!
+//! Tables and entry iterators are opaque handles that the caller frees. Functions that can fail
+//! return a `CstcErrorCode` and, when given a non-null `CstcError`, fill it in with the offset and
+//! a message; messages are freed with `cstc_error_free`. An error can be reused across calls without
+//! freeing it in between: a message left in it is freed before the next one is written.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use serde_construct_classic::{
+    Document, DeserializerOptions, ErrorKind, ErrorWithOffset, PayloadLength, RawValue, SerializerOptions, TypeRegistry, Value,
+};
+
+/// A parsed Hash Table.
+pub struct CstcTable {
+    document: Document,
+    /// Backs the pointers handed out by the last `cstc_table_get*` call.
+    held: Vec<u8>,
+}
+
+/// A snapshot of a table's entries, in file order.
+pub struct CstcEntries {
+    entries: Vec<HeldEntry>,
+    next: usize,
+}
+
+struct HeldEntry {
+    /// The key with a NUL terminator.
+    key: Vec<u8>,
+    value: Value,
+    held: Vec<u8>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstcErrorCode {
+    Ok = 0,
+    NullPointer,
+    /// A key or string argument is not valid UTF-8.
+    InvalidUtf8,
+    NotFound,
+    TypeMismatch,
+    Message,
+    MissingStringTerminator,
+    StringLengthError,
+    UnknownTypeId,
+    InvalidPayload,
+    NumericOverflow,
+    TrailingCharacters,
+    UnexpectedEnd,
+    LengthNotGiven,
+    InvalidKeyType,
+    NonNumericKey,
+    TextEncodingError,
+    InvalidHeader,
+    UnsupportedValue,
+    Io,
+    LimitExceeded,
+}
+
+/// Details of a failed call.
+#[repr(C)]
+pub struct CstcError {
+    pub code: CstcErrorCode,
+    /// Offset into the parsed buffer the error was found at, or -1 if not known.
+    pub offset: i64,
+    /// NUL-terminated UTF-8 description, or null. Owned by the error.
+    pub message: *mut c_char,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstcValueKind {
+    Int,
+    Float,
+    String,
+    /// A value with a type tag other than integer, float or string.
+    Raw,
+    /// A table stored in place of a value, which `cstc_table_parse` can read.
+    Table,
+}
+
+/// A borrowed view of a value.
+#[repr(C)]
+pub struct CstcValue {
+    pub kind: CstcValueKind,
+    pub int_value: i64,
+    pub float_value: f64,
+    /// NUL-terminated UTF-8 text for strings, the payload of raw values, or the bytes of nested tables.
+    pub data: *const u8,
+    /// Length of `data`, not counting the NUL terminator of strings.
+    pub len: usize,
+    /// The type tag of raw values.
+    pub type_id: u32,
+}
+
+struct Failure {
+    code: CstcErrorCode,
+    offset: Option<usize>,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: CstcErrorCode, message: impl Into<String>) -> Self {
+        Failure { code, offset: None, message: message.into() }
+    }
+}
+
+fn error_code(kind: &ErrorKind) -> CstcErrorCode {
+    match kind {
+        ErrorKind::Message(_) => CstcErrorCode::Message,
+        ErrorKind::MissingStringTerminator => CstcErrorCode::MissingStringTerminator,
+        ErrorKind::StringLengthError(..) => CstcErrorCode::StringLengthError,
+        ErrorKind::UnknownTypeId(_) => CstcErrorCode::UnknownTypeId,
+        ErrorKind::InvalidPayload(_) => CstcErrorCode::InvalidPayload,
+        ErrorKind::NumericOverflow => CstcErrorCode::NumericOverflow,
+        ErrorKind::TypeMismatch => CstcErrorCode::TypeMismatch,
+        ErrorKind::TrailingCharacters => CstcErrorCode::TrailingCharacters,
+        ErrorKind::UnexpectedEnd => CstcErrorCode::UnexpectedEnd,
+        ErrorKind::LengthNotGiven => CstcErrorCode::LengthNotGiven,
+        ErrorKind::InvalidKeyType => CstcErrorCode::InvalidKeyType,
+        ErrorKind::NonNumericKey(_) => CstcErrorCode::NonNumericKey,
+        ErrorKind::TextEncodingError => CstcErrorCode::TextEncodingError,
+        ErrorKind::InvalidHeader => CstcErrorCode::InvalidHeader,
+        ErrorKind::UnsupportedValue => CstcErrorCode::UnsupportedValue,
+        ErrorKind::Io(_) => CstcErrorCode::Io,
+        ErrorKind::LimitExceeded(..) => CstcErrorCode::LimitExceeded,
+    }
+}
+
+impl From<ErrorKind> for Failure {
+    fn from(kind: ErrorKind) -> Self {
+        Failure::new(error_code(&kind), kind.to_string())
+    }
+}
+
+impl From<ErrorWithOffset> for Failure {
+    fn from(e: ErrorWithOffset) -> Self {
+        Failure { code: error_code(e.kind()), offset: e.offset(), message: e.to_string() }
+    }
+}
+
+/// Fills in `error` from `result`, freeing any message it already holds, and returns its code.
+unsafe fn report(result: Result<(), Failure>, error: *mut CstcError) -> CstcErrorCode {
+    let failure = match result {
+        Ok(()) => return CstcErrorCode::Ok,
+        Err(failure) => failure,
+    };
+    if let Some(error) = error.as_mut() {
+        error.code = failure.code;
+        error.offset = failure.offset.map_or(-1, |offset| offset as i64);
+        if !error.message.is_null() {
+            drop(CString::from_raw(error.message));
+        }
+        // messages never contain NUL bytes, but don't fail over it if one does
+        error.message = CString::new(failure.message).map_or(ptr::null_mut(), CString::into_raw);
+    }
+    failure.code
+}
+
+unsafe fn table_ref<'a>(table: *const CstcTable) -> Result<&'a CstcTable, Failure> {
+    table.as_ref().ok_or_else(|| Failure::new(CstcErrorCode::NullPointer, "table is null"))
+}
+
+unsafe fn table_mut<'a>(table: *mut CstcTable) -> Result<&'a mut CstcTable, Failure> {
+    table.as_mut().ok_or_else(|| Failure::new(CstcErrorCode::NullPointer, "table is null"))
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(Failure::new(CstcErrorCode::NullPointer, format!("{name} is null")));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| Failure::new(CstcErrorCode::InvalidUtf8, format!("{name} is not valid UTF-8")))
+}
+
+unsafe fn out_arg<'a, T>(out: *mut T, name: &str) -> Result<&'a mut T, Failure> {
+    out.as_mut().ok_or_else(|| Failure::new(CstcErrorCode::NullPointer, format!("{name} is null")))
+}
+
+/// Describes `value`, keeping the bytes `data` points to in `held`.
+fn describe(value: &Value, held: &mut Vec<u8>) -> CstcValue {
+    let mut described = CstcValue {
+        kind: CstcValueKind::Int,
+        int_value: 0,
+        float_value: 0.0,
+        data: ptr::null(),
+        len: 0,
+        type_id: 0,
+    };
+    held.clear();
+    match value {
+        Value::Int(v) => described.int_value = *v,
+        Value::Float(v) => {
+            described.kind = CstcValueKind::Float;
+            described.float_value = *v;
+        },
+        Value::String(s) => {
+            described.kind = CstcValueKind::String;
+            held.extend_from_slice(s.as_bytes());
+            held.push(0);
+            described.data = held.as_ptr();
+            described.len = s.len();
+        },
+        Value::Raw(raw) => {
+            described.kind = CstcValueKind::Raw;
+            held.extend_from_slice(&raw.payload);
+            described.data = held.as_ptr();
+            described.len = raw.payload.len();
+            described.type_id = raw.type_id;
+        },
+        Value::Table(table) => {
+            described.kind = CstcValueKind::Table;
+            let options = SerializerOptions { nested_tables: true, ..Default::default() };
+            // tables that were read from bytes can always be written back
+            if let Ok(bytes) = serde_construct_classic::to_bytes_with_options(table, options) {
+                *held = bytes;
+            }
+            described.data = held.as_ptr();
+            described.len = held.len();
+        },
+    }
+    described
+}
+
+fn with_nul(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len() + 1);
+    bytes.extend_from_slice(s.as_bytes());
+    bytes.push(0);
+    bytes
+}
+
+/// Creates an empty table.
+#[no_mangle]
+pub extern "C" fn cstc_table_new() -> *mut CstcTable {
+    Box::into_raw(Box::new(CstcTable { document: Document::new(), held: Vec::new() }))
+}
+
+/// Parses a Hash Table from `len` bytes at `data`, storing the new table in `out_table`.
+/// Values with unknown type tags are kept as raw values.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes, `out_table` must be valid for writes,
+/// and `error` must be null or a zero-initialized error or one filled in by this library.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_parse(
+    data: *const u8,
+    len: usize,
+    out_table: *mut *mut CstcTable,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        if data.is_null() {
+            return Err(Failure::new(CstcErrorCode::NullPointer, "data is null"));
+        }
+        let out_table = out_arg(out_table, "out_table")?;
+        let bytes = std::slice::from_raw_parts(data, len);
+        let options = DeserializerOptions {
+            type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+            ..Default::default()
+        };
+        let document = Document::parse_with_options(bytes, options)?;
+        *out_table = Box::into_raw(Box::new(CstcTable { document, held: Vec::new() }));
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Frees a table. Does nothing if `table` is null.
+///
+/// # Safety
+/// `table` must be null or a table that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_free(table: *mut CstcTable) {
+    if !table.is_null() {
+        drop(Box::from_raw(table));
+    }
+}
+
+/// The number of entries, including duplicate keys.
+///
+/// # Safety
+/// `table` must be a valid table.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_len(table: *const CstcTable) -> usize {
+    table.as_ref().map_or(0, |table| table.document.len())
+}
+
+/// Whether the table has an entry with `key`.
+///
+/// # Safety
+/// `table` must be a valid table and `key` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_contains(table: *const CstcTable, key: *const c_char) -> bool {
+    match (table_ref(table), str_arg(key, "key")) {
+        (Ok(table), Ok(key)) => table.document.contains_key(key),
+        _ => false,
+    }
+}
+
+/// Looks up the first entry with `key`. Pointers in `out_value` stay valid until the next
+/// `cstc_table_get*` call on the table, or until it is edited or freed.
+///
+/// # Safety
+/// `table` must be a valid table, `key` a NUL-terminated string, `out_value` valid for writes,
+/// and `error` null or a zero-initialized error or one filled in by this library.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_get(
+    table: *mut CstcTable,
+    key: *const c_char,
+    out_value: *mut CstcValue,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_mut(table)?;
+        let out_value = out_arg(out_value, "out_value")?;
+        let value = get(table, str_arg(key, "key")?)?;
+        *out_value = describe(&value, &mut table.held);
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Gets the integer value of `key`, failing with `TYPE_MISMATCH` for other types.
+///
+/// # Safety
+/// As for `cstc_table_get`.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_get_int(
+    table: *mut CstcTable,
+    key: *const c_char,
+    out_value: *mut i64,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_mut(table)?;
+        let out_value = out_arg(out_value, "out_value")?;
+        match get(table, str_arg(key, "key")?)? {
+            Value::Int(v) => *out_value = v,
+            _ => return Err(mismatch("an integer")),
+        }
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Gets the float value of `key`, failing with `TYPE_MISMATCH` for other types.
+///
+/// # Safety
+/// As for `cstc_table_get`.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_get_float(
+    table: *mut CstcTable,
+    key: *const c_char,
+    out_value: *mut f64,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_mut(table)?;
+        let out_value = out_arg(out_value, "out_value")?;
+        match get(table, str_arg(key, "key")?)? {
+            Value::Float(v) => *out_value = v,
+            _ => return Err(mismatch("a float")),
+        }
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Gets the string value of `key` as NUL-terminated UTF-8, failing with `TYPE_MISMATCH` for other types.
+/// The string stays valid for as long as a value from `cstc_table_get` would.
+///
+/// # Safety
+/// As for `cstc_table_get`.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_get_string(
+    table: *mut CstcTable,
+    key: *const c_char,
+    out_value: *mut *const c_char,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_mut(table)?;
+        let out_value = out_arg(out_value, "out_value")?;
+        let value = get(table, str_arg(key, "key")?)?;
+        if !matches!(value, Value::String(_)) {
+            return Err(mismatch("a string"));
+        }
+        *out_value = describe(&value, &mut table.held).data.cast();
+        Ok(())
+    })();
+    report(result, error)
+}
+
+fn get(table: &CstcTable, key: &str) -> Result<Value, Failure> {
+    table.document.get(key).ok_or_else(|| Failure::new(CstcErrorCode::NotFound, format!("No entry with key \"{key}\"")))
+}
+
+fn mismatch(expected: &str) -> Failure {
+    Failure::new(CstcErrorCode::TypeMismatch, format!("The value is not {expected}"))
+}
+
+unsafe fn set(table: *mut CstcTable, key: *const c_char, value: Result<Value, Failure>, error: *mut CstcError) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_mut(table)?;
+        table.document.set(str_arg(key, "key")?, value?)?;
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Sets `key` to an integer, replacing the first entry with that key or appending a new one.
+///
+/// # Safety
+/// `table` must be a valid table, `key` a NUL-terminated string, and `error` null or a zero-initialized
+/// error or one filled in by this library.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_set_int(
+    table: *mut CstcTable,
+    key: *const c_char,
+    value: i64,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    set(table, key, Ok(Value::Int(value)), error)
+}
+
+/// Sets `key` to a float, like `cstc_table_set_int`.
+///
+/// # Safety
+/// As for `cstc_table_set_int`.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_set_float(
+    table: *mut CstcTable,
+    key: *const c_char,
+    value: f64,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    set(table, key, Ok(Value::Float(value)), error)
+}
+
+/// Sets `key` to a NUL-terminated UTF-8 string, like `cstc_table_set_int`.
+/// Fails with `TEXT_ENCODING_ERROR` if the string can't be stored as windows-1252.
+///
+/// # Safety
+/// As for `cstc_table_set_int`, and `value` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_set_string(
+    table: *mut CstcTable,
+    key: *const c_char,
+    value: *const c_char,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let value = str_arg(value, "value").map(Value::from);
+    set(table, key, value, error)
+}
+
+/// Sets `key` to a value with type tag `type_id` and a `len`-byte payload, like `cstc_table_set_int`.
+///
+/// # Safety
+/// As for `cstc_table_set_int`, and `payload` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_set_raw(
+    table: *mut CstcTable,
+    key: *const c_char,
+    type_id: u32,
+    payload: *const u8,
+    len: usize,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let value = if payload.is_null() && len > 0 {
+        Err(Failure::new(CstcErrorCode::NullPointer, "payload is null"))
+    } else {
+        let payload = if len == 0 { Vec::new() } else { std::slice::from_raw_parts(payload, len).to_vec() };
+        Ok(Value::Raw(RawValue { type_id, payload }))
+    };
+    set(table, key, value, error)
+}
+
+/// Removes the first entry with `key`, returning whether there was one.
+///
+/// # Safety
+/// `table` must be a valid table and `key` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_remove(table: *mut CstcTable, key: *const c_char) -> bool {
+    match (table_mut(table), str_arg(key, "key")) {
+        (Ok(table), Ok(key)) => table.document.remove(key).is_some(),
+        _ => false,
+    }
+}
+
+/// Serializes the table into a new buffer, which must be released with `cstc_bytes_free`.
+/// Entries that weren't edited keep their original bytes.
+///
+/// # Safety
+/// `table` must be a valid table, `out_data` and `out_len` valid for writes,
+/// and `error` null or a zero-initialized error or one filled in by this library.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_table_to_bytes(
+    table: *const CstcTable,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+    error: *mut CstcError,
+) -> CstcErrorCode {
+    let result = (|| {
+        let table = table_ref(table)?;
+        let out_data = out_arg(out_data, "out_data")?;
+        let out_len = out_arg(out_len, "out_len")?;
+        let bytes = table.document.to_bytes().into_boxed_slice();
+        *out_len = bytes.len();
+        *out_data = Box::into_raw(bytes).cast();
+        Ok(())
+    })();
+    report(result, error)
+}
+
+/// Frees a buffer returned by `cstc_table_to_bytes`. Does nothing if `data` is null.
+///
+/// # Safety
+/// `data` and `len` must be exactly as returned by `cstc_table_to_bytes`, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_bytes_free(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// Starts iterating over the table's entries. The iterator holds a copy of the entries,
+/// so the table may be edited or freed while iterating.
+///
+/// # Safety
+/// `table` must be a valid table.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_entries_new(table: *const CstcTable) -> *mut CstcEntries {
+    let Ok(table) = table_ref(table) else { return ptr::null_mut() };
+    let entries = table.document.iter()
+        .map(|(key, value)| HeldEntry { key: with_nul(key), value, held: Vec::new() })
+        .collect();
+    Box::into_raw(Box::new(CstcEntries { entries, next: 0 }))
+}
+
+/// Advances to the next entry, returning false once there are none left. `out_key` and the
+/// pointers in `out_value` stay valid until the iterator is freed.
+///
+/// # Safety
+/// `entries` must be a valid iterator, and `out_key` and `out_value` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_entries_next(
+    entries: *mut CstcEntries,
+    out_key: *mut *const c_char,
+    out_value: *mut CstcValue,
+) -> bool {
+    let (Some(entries), Some(out_key), Some(out_value)) = (entries.as_mut(), out_key.as_mut(), out_value.as_mut()) else {
+        return false;
+    };
+    let Some(entry) = entries.entries.get_mut(entries.next) else { return false };
+    entries.next += 1;
+    *out_key = entry.key.as_ptr().cast();
+    *out_value = describe(&entry.value, &mut entry.held);
+    true
+}
+
+/// Frees an entry iterator. Does nothing if `entries` is null.
+///
+/// # Safety
+/// `entries` must be null or an iterator that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_entries_free(entries: *mut CstcEntries) {
+    if !entries.is_null() {
+        drop(Box::from_raw(entries));
+    }
+}
+
+/// Frees the message of an error filled in by a failed call, and resets the error.
+///
+/// # Safety
+/// `error` must be null or point to an error that is zero-initialized or was filled in by this library.
+#[no_mangle]
+pub unsafe extern "C" fn cstc_error_free(error: *mut CstcError) {
+    let Some(error) = error.as_mut() else { return };
+    if !error.message.is_null() {
+        drop(CString::from_raw(error.message));
+    }
+    error.code = CstcErrorCode::Ok;
+    error.offset = -1;
+    error.message = ptr::null_mut();
+}
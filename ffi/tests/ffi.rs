@@ -0,0 +1,199 @@
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+use cstc::*;
+use serde_construct_classic::{table, to_bytes, to_bytes_with_options, HashTable, RawValue, SerializerOptions};
+
+fn key(s: &CStr) -> *const c_char {
+    s.as_ptr()
+}
+
+fn empty_error() -> CstcError {
+    CstcError { code: CstcErrorCode::Ok, offset: -1, message: ptr::null_mut() }
+}
+
+fn original() -> Vec<u8> {
+    let mut table = HashTable::new();
+    table.push("name", "Bob");
+    table.push("hp", 100);
+    table.push("blob", RawValue { type_id: 9, payload: vec![1, 2, 3] });
+    table.push("speed", 2.5);
+    to_bytes(&table).unwrap()
+}
+
+unsafe fn parse(bytes: &[u8]) -> *mut CstcTable {
+    let mut table = ptr::null_mut();
+    let code = cstc_table_parse(bytes.as_ptr(), bytes.len(), &mut table, ptr::null_mut());
+    assert_eq!(code, CstcErrorCode::Ok);
+    table
+}
+
+unsafe fn serialize(table: *const CstcTable) -> Vec<u8> {
+    let mut data = ptr::null_mut();
+    let mut len = 0;
+    assert_eq!(cstc_table_to_bytes(table, &mut data, &mut len, ptr::null_mut()), CstcErrorCode::Ok);
+    let bytes = std::slice::from_raw_parts(data, len).to_vec();
+    cstc_bytes_free(data, len);
+    bytes
+}
+
+#[test]
+fn parse_and_serialize_round_trips() {
+    let bytes = original();
+    unsafe {
+        let table = parse(&bytes);
+        assert_eq!(cstc_table_len(table), 4);
+        assert_eq!(serialize(table), bytes);
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn typed_getters() {
+    unsafe {
+        let table = parse(&original());
+        let mut hp = 0;
+        assert_eq!(cstc_table_get_int(table, key(c"hp"), &mut hp, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(hp, 100);
+        let mut speed = 0.0;
+        assert_eq!(cstc_table_get_float(table, key(c"speed"), &mut speed, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(speed, 2.5);
+        let mut name = ptr::null();
+        assert_eq!(cstc_table_get_string(table, key(c"name"), &mut name, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(CStr::from_ptr(name), c"Bob");
+
+        let mut error = empty_error();
+        assert_eq!(cstc_table_get_int(table, key(c"name"), &mut hp, &mut error), CstcErrorCode::TypeMismatch);
+        assert_eq!(error.code, CstcErrorCode::TypeMismatch);
+        cstc_error_free(&mut error);
+        assert_eq!(cstc_table_get_int(table, key(c"missing"), &mut hp, &mut error), CstcErrorCode::NotFound);
+        cstc_error_free(&mut error);
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn raw_values_are_exposed() {
+    unsafe {
+        let table = parse(&original());
+        let mut value = std::mem::zeroed::<CstcValue>();
+        assert_eq!(cstc_table_get(table, key(c"blob"), &mut value, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(value.kind, CstcValueKind::Raw);
+        assert_eq!(value.type_id, 9);
+        assert_eq!(std::slice::from_raw_parts(value.data, value.len), [1, 2, 3]);
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn nested_tables_are_exposed_as_bytes() {
+    let options = SerializerOptions { nested_tables: true, ..Default::default() };
+    let bytes = to_bytes_with_options(&table! { "name" => "Bob", "inner" => table! { "x" => 7 } }, options).unwrap();
+    unsafe {
+        let table = parse(&bytes);
+        let mut value = std::mem::zeroed::<CstcValue>();
+        assert_eq!(cstc_table_get(table, key(c"inner"), &mut value, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(value.kind, CstcValueKind::Table);
+        let inner = parse(std::slice::from_raw_parts(value.data, value.len));
+        let mut x = 0;
+        assert_eq!(cstc_table_get_int(inner, key(c"x"), &mut x, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(x, 7);
+        assert_eq!(serialize(table), bytes);
+        cstc_table_free(inner);
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn setters_edit_the_table() {
+    unsafe {
+        let table = cstc_table_new();
+        assert_eq!(cstc_table_set_int(table, key(c"hp"), 100, ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(cstc_table_set_string(table, key(c"name"), key(c"Bob"), ptr::null_mut()), CstcErrorCode::Ok);
+        assert_eq!(cstc_table_set_float(table, key(c"speed"), 2.5, ptr::null_mut()), CstcErrorCode::Ok);
+        let payload = [1, 2, 3];
+        let code = cstc_table_set_raw(table, key(c"blob"), 9, payload.as_ptr(), payload.len(), ptr::null_mut());
+        assert_eq!(code, CstcErrorCode::Ok);
+        assert_eq!(serialize(table), to_bytes(&{
+            let mut expected = HashTable::new();
+            expected.push("hp", 100);
+            expected.push("name", "Bob");
+            expected.push("speed", 2.5);
+            expected.push("blob", RawValue { type_id: 9, payload: vec![1, 2, 3] });
+            expected
+        }).unwrap());
+
+        assert!(cstc_table_remove(table, key(c"hp")));
+        assert!(!cstc_table_contains(table, key(c"hp")));
+        assert!(!cstc_table_remove(table, key(c"hp")));
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn unencodable_strings_are_rejected() {
+    unsafe {
+        let table = cstc_table_new();
+        let mut error = empty_error();
+        let code = cstc_table_set_string(table, key(c"name"), key(c"\u{3042}"), &mut error);
+        assert_eq!(code, CstcErrorCode::TextEncodingError);
+        assert!(!error.message.is_null());
+        cstc_error_free(&mut error);
+        assert!(error.message.is_null());
+        cstc_table_free(table);
+    }
+}
+
+#[test]
+fn entries_iterate_in_file_order() {
+    unsafe {
+        let table = parse(&original());
+        let entries = cstc_entries_new(table);
+        // the iterator holds its own copy of the entries
+        cstc_table_free(table);
+        let mut keys = Vec::new();
+        let mut key = ptr::null();
+        let mut value = std::mem::zeroed::<CstcValue>();
+        while cstc_entries_next(entries, &mut key, &mut value) {
+            keys.push(CStr::from_ptr(key).to_str().unwrap().to_owned());
+            if keys.last().unwrap() == "name" {
+                assert_eq!(value.kind, CstcValueKind::String);
+                assert_eq!(CStr::from_ptr(value.data.cast()), c"Bob");
+            }
+        }
+        assert_eq!(keys, ["name", "hp", "blob", "speed"]);
+        cstc_entries_free(entries);
+    }
+}
+
+#[test]
+fn parse_errors_have_offsets() {
+    let mut bytes = original();
+    bytes.truncate(16);
+    unsafe {
+        let mut table = ptr::null_mut();
+        let mut error = empty_error();
+        let code = cstc_table_parse(bytes.as_ptr(), bytes.len(), &mut table, &mut error);
+        assert_eq!(code, CstcErrorCode::StringLengthError);
+        assert!(table.is_null());
+        assert_eq!(error.offset, 10);
+        assert!(CStr::from_ptr(error.message).to_str().unwrap().contains("too long"));
+        cstc_error_free(&mut error);
+    }
+}
+
+#[test]
+fn errors_can_be_reused_across_calls() {
+    unsafe {
+        let table = cstc_table_new();
+        let mut error = empty_error();
+        let code = cstc_table_set_string(table, key(c"name"), key(c"\u{3042}"), &mut error);
+        assert_eq!(code, CstcErrorCode::TextEncodingError);
+        let code = cstc_table_set_int(table, ptr::null(), 1, &mut error);
+        assert_eq!(code, CstcErrorCode::NullPointer);
+        assert_eq!(error.code, CstcErrorCode::NullPointer);
+        assert!(CStr::from_ptr(error.message).to_str().unwrap().contains("key is null"));
+        cstc_error_free(&mut error);
+        cstc_table_free(table);
+    }
+}
@@ -0,0 +1,170 @@
+//! Python bindings, mapping Hash Tables to and from ordered dicts, or lists of pairs to keep duplicate keys.
+//!
+//! Integers, floats and strings map to `int`, `float` and `str`, values with other type tags
+//! to `RawValue`, and nested tables to dicts (or lists of pairs) of their own. Errors are raised as `construct_classic.Error`, whose `offset` attribute holds the
+//! offset into the input the error was found at, if any.
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyFloat, PyInt, PyList, PyString};
+use pyo3::IntoPyObjectExt;
+use serde_construct_classic::{
+    self as cstc, DeserializerOptions, ErrorKind, ErrorWithOffset, HashTable, PayloadLength, SerializerOptions,
+    TypeRegistry, Value,
+};
+
+create_exception!(construct_classic, Error, PyValueError, "A table could not be read or written.");
+
+/// A value with a type tag other than integer, float or string, kept byte for byte.
+#[pyclass(module = "construct_classic", name = "RawValue", eq, frozen)]
+#[derive(Clone, PartialEq)]
+pub struct PyRawValue {
+    #[pyo3(get)]
+    type_id: u32,
+    #[pyo3(get)]
+    payload: Vec<u8>,
+}
+
+#[pymethods]
+impl PyRawValue {
+    #[new]
+    fn new(type_id: u32, payload: Vec<u8>) -> Self {
+        PyRawValue { type_id, payload }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RawValue({}, {:?})", self.type_id, self.payload)
+    }
+}
+
+fn error(py: Python<'_>, message: String, offset: Option<usize>) -> PyErr {
+    let err = Error::new_err(message);
+    // a fresh exception instance always accepts new attributes
+    err.value(py).setattr("offset", offset).expect("setting the offset of an exception");
+    err
+}
+
+fn read_error(py: Python<'_>, e: ErrorWithOffset) -> PyErr {
+    error(py, e.to_string(), e.offset())
+}
+
+fn write_error(py: Python<'_>, e: ErrorKind) -> PyErr {
+    error(py, e.to_string(), None)
+}
+
+fn to_python<'py>(py: Python<'py>, value: Value, pairs: bool) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Int(v) => v.into_bound_py_any(py),
+        Value::Float(v) => v.into_bound_py_any(py),
+        Value::String(v) => v.into_bound_py_any(py),
+        Value::Raw(raw) => PyRawValue { type_id: raw.type_id, payload: raw.payload }.into_bound_py_any(py),
+        Value::Table(table) => table_to_python(py, table, pairs),
+    }
+}
+
+/// A dict of the entries in file order, keeping the first of each key, or a list of all of them as pairs.
+fn table_to_python<'py>(py: Python<'py>, table: HashTable, pairs: bool) -> PyResult<Bound<'py, PyAny>> {
+    if pairs {
+        let list = PyList::empty(py);
+        for (key, value) in table {
+            list.append((key, to_python(py, value, pairs)?))?;
+        }
+        return Ok(list.into_any());
+    }
+    let dict = PyDict::new(py);
+    for (key, value) in table {
+        if !dict.contains(&key)? {
+            dict.set_item(key, to_python(py, value, pairs)?)?;
+        }
+    }
+    Ok(dict.into_any())
+}
+
+/// The entries of a mapping, or of an iterable of `(key, value)` pairs.
+fn table_from_python(table: &Bound<'_, PyAny>, nested_tables: bool) -> PyResult<HashTable> {
+    let items = if table.hasattr("items")? { table.call_method0("items")? } else { table.clone() };
+    let mut entries = HashTable::new();
+    for item in items.try_iter()? {
+        let (key, value): (String, Bound<'_, PyAny>) = item?.extract()?;
+        let value = from_python(&key, &value, nested_tables)?;
+        entries.push(key, value);
+    }
+    Ok(entries)
+}
+
+fn from_python(key: &str, value: &Bound<'_, PyAny>, nested_tables: bool) -> PyResult<Value> {
+    if let Ok(raw) = value.downcast::<PyRawValue>() {
+        let raw = raw.get();
+        return Ok(Value::Raw(cstc::RawValue { type_id: raw.type_id, payload: raw.payload.clone() }));
+    }
+    // bools are ints in Python, and are stored as 0 and 1 like the serializer does
+    if value.is_instance_of::<PyInt>() {
+        return Ok(Value::Int(value.extract()?));
+    }
+    if value.is_instance_of::<PyFloat>() {
+        return Ok(Value::Float(value.extract()?));
+    }
+    if value.is_instance_of::<PyString>() {
+        return Ok(Value::String(value.extract()?));
+    }
+    if nested_tables && (value.is_instance_of::<PyDict>() || value.is_instance_of::<PyList>()) {
+        return Ok(Value::Table(table_from_python(value, nested_tables)?));
+    }
+    let type_name = value.get_type().name()?;
+    Err(PyTypeError::new_err(format!("Value of \"{key}\" has unsupported type {type_name}")))
+}
+
+/// Reads a table from bytes into a dict in file order. Of entries with the same key, the dict keeps the first.
+/// With `pairs=True`, returns a list of `(key, value)` tuples instead, which keeps all of them.
+/// Nested tables are read the same way.
+#[pyfunction]
+#[pyo3(signature = (data, *, pairs = false))]
+fn loads<'py>(py: Python<'py>, data: &[u8], pairs: bool) -> PyResult<Bound<'py, PyAny>> {
+    let options = DeserializerOptions {
+        type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+        ..Default::default()
+    };
+    let table: HashTable = cstc::from_bytes_with_options(data, options).map_err(|e| read_error(py, e))?;
+    table_to_python(py, table, pairs)
+}
+
+/// Writes a mapping of strings to ints, floats, strings and `RawValue`s as a table,
+/// or an iterable of `(key, value)` pairs like `loads(data, pairs=True)` returns.
+/// Dicts and lists of pairs in place of values are written as nested tables with `nested_tables=True`,
+/// and rejected with a `TypeError` otherwise, as Construct itself can't read them.
+#[pyfunction]
+#[pyo3(signature = (table, *, nested_tables = false))]
+fn dumps<'py>(py: Python<'py>, table: &Bound<'py, PyAny>, nested_tables: bool) -> PyResult<Bound<'py, PyBytes>> {
+    let entries = table_from_python(table, nested_tables)?;
+    let options = SerializerOptions { nested_tables, ..Default::default() };
+    let bytes = cstc::to_bytes_with_options(&entries, options).map_err(|e| write_error(py, e))?;
+    Ok(PyBytes::new(py, &bytes))
+}
+
+/// Reads a table from a binary file object, like `loads`.
+#[pyfunction]
+#[pyo3(signature = (fp, *, pairs = false))]
+fn load<'py>(py: Python<'py>, fp: &Bound<'py, PyAny>, pairs: bool) -> PyResult<Bound<'py, PyAny>> {
+    let data = fp.call_method0("read")?;
+    loads(py, data.extract()?, pairs)
+}
+
+/// Writes a table to a binary file object, like `dumps`.
+#[pyfunction]
+#[pyo3(signature = (table, fp, *, nested_tables = false))]
+fn dump<'py>(py: Python<'py>, table: &Bound<'py, PyAny>, fp: &Bound<'py, PyAny>, nested_tables: bool) -> PyResult<()> {
+    fp.call_method1("write", (dumps(py, table, nested_tables)?,))?;
+    Ok(())
+}
+
+#[pymodule]
+pub fn construct_classic(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("Error", m.py().get_type::<Error>())?;
+    m.add_class::<PyRawValue>()?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(dump, m)?)?;
+    Ok(())
+}
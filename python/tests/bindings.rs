@@ -0,0 +1,117 @@
+use std::ffi::CStr;
+
+use construct_classic::construct_classic;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+use serde_construct_classic::{to_bytes, HashTable, RawValue};
+
+/// Runs `code` with the module bound to `cc` and `data` bound to a sample table.
+fn run(code: &CStr) {
+    let mut table = HashTable::new();
+    table.push("name", "Bob");
+    table.push("hp", 100);
+    table.push("hp", 50);
+    table.push("blob", RawValue { type_id: 9, payload: vec![1, 2, 3] });
+    table.push("speed", 2.5);
+    let data = to_bytes(&table).unwrap();
+
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "construct_classic").unwrap();
+        construct_classic(&module).unwrap();
+        let globals = PyDict::new(py);
+        globals.set_item("cc", module).unwrap();
+        globals.set_item("data", pyo3::types::PyBytes::new(py, &data)).unwrap();
+        if let Err(e) = py.run(code, Some(&globals), None) {
+            e.display(py);
+            panic!("Python code failed");
+        }
+    });
+}
+
+#[test]
+fn loads_keeps_types_and_order() {
+    run(cr#"
+table = cc.loads(data)
+assert list(table) == ["name", "hp", "blob", "speed"], table
+assert table["name"] == "Bob"
+assert type(table["hp"]) is int and table["hp"] == 100
+assert type(table["speed"]) is float and table["speed"] == 2.5
+assert table["blob"] == cc.RawValue(9, b"\x01\x02\x03")
+assert table["blob"].type_id == 9 and table["blob"].payload == b"\x01\x02\x03"
+"#);
+}
+
+#[test]
+fn dumps_round_trips() {
+    run(cr#"
+table = cc.loads(data)
+assert cc.loads(cc.dumps(table)) == table
+assert cc.dumps({"one": 1.0}) != cc.dumps({"one": 1})
+assert cc.loads(cc.dumps({"flag": True})) == {"flag": 1}
+"#);
+}
+
+#[test]
+fn nested_tables_are_dicts() {
+    run(cr#"
+nested = {"name": "Bob", "inner": {"x": 7, "deeper": {}}}
+try:
+    cc.dumps(nested)
+    raise AssertionError("nested tables were written without nested_tables")
+except TypeError:
+    pass
+data = cc.dumps(nested, nested_tables=True)
+assert cc.loads(data) == nested
+assert cc.loads(data, pairs=True)[1] == ("inner", [("x", 7), ("deeper", [])])
+assert cc.dumps(cc.loads(data, pairs=True), nested_tables=True) == data
+"#);
+}
+
+#[test]
+fn pairs_keep_duplicate_keys() {
+    run(cr#"
+assert cc.loads(data)["hp"] == 100
+pairs = cc.loads(data, pairs=True)
+assert [key for key, _ in pairs] == ["name", "hp", "hp", "blob", "speed"], pairs
+assert pairs[2] == ("hp", 50)
+assert cc.dumps(pairs) == data
+"#);
+}
+
+#[test]
+fn load_and_dump_use_file_objects() {
+    run(cr#"
+import io
+fp = io.BytesIO()
+cc.dump({"a": "b"}, fp)
+fp.seek(0)
+assert cc.load(fp) == {"a": "b"}
+"#);
+}
+
+#[test]
+fn errors_carry_offsets() {
+    run(cr#"
+try:
+    cc.loads(data[:16])
+except cc.Error as e:
+    assert e.offset == 10, e.offset
+    assert isinstance(e, ValueError)
+else:
+    raise AssertionError("no error")
+
+try:
+    cc.dumps({"name": "あ"})
+except cc.Error as e:
+    assert e.offset is None
+else:
+    raise AssertionError("no error")
+
+try:
+    cc.dumps({"list": []})
+except TypeError:
+    pass
+else:
+    raise AssertionError("no error")
+"#);
+}
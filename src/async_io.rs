@@ -0,0 +1,169 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::constants::*;
+use crate::de::{from_bytes_with_options, DeserializerOptions, Framing};
+use crate::error::{ErrorKind, ErrorWithOffset};
+use crate::registry::PayloadLength;
+use crate::ser::{to_bytes_with_options, SerializerOptions};
+
+/// Reads a table from `reader` and deserializes it.
+///
+/// Entries are read one at a time using their length prefixes, and reading stops after the last
+/// entry the header declares, so `reader` can be a socket or pipe that stays open. Payloads of
+/// registered types measured with `PayloadLength::UntilNextKey` or `PayloadLength::Custom` can't be
+/// sized before they're read, so reaching one reads the rest of the stream.
+///
+/// The table is read into memory in full before it is parsed, so error offsets are relative to
+/// where reading started. With `Limits::max_total_bytes` set, no more than the budget is read, and a
+/// key or string longer than its limit is reported without reading it.
+pub async fn from_async_reader<R, T>(reader: R) -> Result<T, ErrorWithOffset>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_async_reader_with_options(reader, DeserializerOptions::default()).await
+}
+
+pub async fn from_async_reader_with_options<R, T>(reader: R, options: DeserializerOptions) -> Result<T, ErrorWithOffset>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut table = TableReader { reader, bytes: Vec::new(), max_total_bytes: options.limits.max_total_bytes };
+    table.read_table(&options).await?;
+    from_bytes_with_options(&table.bytes, options)
+}
+
+/// Collects the bytes of one table from a stream.
+///
+/// Reading stops early when the stream ends or the input stops making sense; the deserializer then
+/// reports the problem with the bytes collected so far.
+struct TableReader<R> {
+    reader: R,
+    bytes: Vec<u8>,
+    max_total_bytes: Option<usize>,
+}
+
+impl<R: AsyncRead + Unpin> TableReader<R> {
+    async fn read_table(&mut self, options: &DeserializerOptions) -> Result<(), ErrorKind> {
+        if !self.fill(MAGIC.len() + 4).await? || self.bytes[..MAGIC.len()] != MAGIC[..] {
+            return Ok(());
+        }
+        let count = read_u32(&self.bytes[self.bytes.len() - 4..]);
+        self.read_entries(count, 1, options).await?;
+        Ok(())
+    }
+
+    /// Reads the entries of a table `depth` levels deep, returning whether all of them were there.
+    async fn read_entries(&mut self, count: u32, depth: usize, options: &DeserializerOptions) -> Result<bool, ErrorKind> {
+        // one entry past the limit is enough for the deserializer to report it
+        let count = options.limits.max_entries.map_or(count as usize, |max| (count as usize).min(max.saturating_add(1)));
+        for _ in 0..count {
+            if !self.read_entry(depth, options).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads a key and its value, returning whether all of it was there.
+    ///
+    /// Lengths over the limits aren't read past, so that the deserializer reports them before the
+    /// stream is read any further.
+    async fn read_entry(&mut self, depth: usize, options: &DeserializerOptions) -> Result<bool, ErrorKind> {
+        let limits = &options.limits;
+        let key = self.bytes.len();
+        if !self.fill_to(key + 4).await? {
+            return Ok(false);
+        }
+        let key_len = read_u32(&self.bytes[key..]);
+        if limits.check_str_len(true, key_len, key).is_err() || !self.fill_to(key + 4 + key_len as usize).await? {
+            return Ok(false);
+        }
+        let value = self.bytes.len();
+        if !self.fill_to(value + 4).await? {
+            return Ok(false);
+        }
+        if self.bytes[value..value + 4] == MAGIC[..4] {
+            // enough to tell a nested table's header from a type tag
+            self.fill_to(value + MAGIC.len()).await?;
+        }
+        let Some(framing) = Framing::of(&self.bytes[value..], &options.type_registry) else { return Ok(false) };
+        let end = match framing {
+            Framing::Table => {
+                if limits.max_depth.is_some_and(|max| depth >= max) || !self.fill_to(value + MAGIC.len() + 4).await? {
+                    return Ok(false);
+                }
+                let count = read_u32(&self.bytes[value + MAGIC.len()..]);
+                return Box::pin(self.read_entries(count, depth + 1, options)).await;
+            },
+            Framing::Number => value + 12,
+            Framing::String | Framing::Raw(PayloadLength::Prefixed) => {
+                if !self.fill_to(value + 8).await? {
+                    return Ok(false);
+                }
+                let len = read_u32(&self.bytes[value + 4..]);
+                let checked = match framing {
+                    Framing::String => limits.check_str_len(false, len, value + 4),
+                    _ => limits.check_payload_len(len, value + 4),
+                };
+                if checked.is_err() {
+                    return Ok(false);
+                }
+                value + 8 + len as usize
+            },
+            Framing::Raw(PayloadLength::Fixed(len)) => value + 4 + len,
+            Framing::Raw(PayloadLength::UntilNextKey | PayloadLength::Custom(_)) => {
+                self.fill(usize::MAX).await?;
+                return Ok(false);
+            },
+            Framing::Unknown => return Ok(false),
+        };
+        self.fill_to(end).await
+    }
+
+    /// Reads the stream up to `end` bytes from where the table started, returning whether they were all there.
+    async fn fill_to(&mut self, end: usize) -> Result<bool, ErrorKind> {
+        self.fill(end.saturating_sub(self.bytes.len())).await
+    }
+
+    /// Appends the next `len` bytes of the stream, returning whether they were all there.
+    /// With a byte budget, reading stops one byte past it, which the deserializer reports.
+    async fn fill(&mut self, len: usize) -> Result<bool, ErrorKind> {
+        let available = match self.max_total_bytes {
+            Some(max) => max.saturating_add(1).saturating_sub(self.bytes.len()),
+            None => usize::MAX,
+        };
+        let want = len.min(available);
+        let read = (&mut self.reader).take(want as u64).read_to_end(&mut self.bytes).await?;
+        Ok(read == len)
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+/// Serializes `value` and writes it to `writer`, flushing it afterwards.
+pub async fn to_async_writer<W, T>(writer: W, value: &T) -> Result<(), ErrorKind>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    to_async_writer_with_options(writer, value, SerializerOptions::default()).await
+}
+
+pub async fn to_async_writer_with_options<W, T>(mut writer: W, value: &T, options: SerializerOptions) -> Result<(), ErrorKind>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let bytes = to_bytes_with_options(value, options)?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
@@ -1,10 +1,8 @@
 use std::{fs, path::{Path, PathBuf}, process::exit};
-use clap::{command, Parser, Subcommand};
-
-mod ser;
-mod de;
-mod constants;
-mod error;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use serde_transcode::Transcoder;
+use serde_construct_classic::{self as cstc, DeserializerOptions, HashTable, KeyOrder, PayloadLength, SerializerOptions, TokenOptions, TypeRegistry, Value};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -20,14 +18,54 @@ enum Commands {
     TableToJson {
         input: PathBuf,
         output: Option<PathBuf>,
+        /// Write the string value of KEY as an array of its tokens (can be repeated)
+        #[arg(long = "expand-tokens", value_name = "KEY")]
+        expand_tokens: Vec<String>,
+        /// The separator of the tokens expanded by --expand-tokens
+        #[arg(long = "token-separator", default_value_t = ',')]
+        token_separator: char,
     },
     /// Convert JSON file to Construct Classic Hash Table
     JsonToTable {
         input: PathBuf,
         output: Option<PathBuf>,
+        /// Write the entries ordered by key
+        #[arg(long = "sort-keys", value_enum, num_args = 0..=1, default_missing_value = "unicode")]
+        sort_keys: Option<SortOrder>,
+        /// Write objects in place of values as nested tables, which Construct itself can't read
+        #[arg(long = "nested-tables")]
+        nested_tables: bool,
+    },
+    /// Rewrite Construct Classic Hash Table with its entries ordered by key
+    Fmt {
+        input: PathBuf,
+        /// Defaults to overwriting the input file
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value = "unicode")]
+        order: SortOrder,
     },
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SortOrder {
+    /// By Unicode code point
+    Unicode,
+    /// By the Windows-1252 bytes stored in the file
+    Bytes,
+    /// Numeric-aware, so that "level2" comes before "level10"
+    Natural,
+}
+
+impl From<SortOrder> for KeyOrder {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Unicode => KeyOrder::Unicode,
+            SortOrder::Bytes => KeyOrder::Windows1252,
+            SortOrder::Natural => KeyOrder::Natural,
+        }
+    }
+}
+
 fn main() {
     match main1() {
         Ok(_) => {
@@ -44,26 +82,76 @@ fn main1() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     match args.command {
-        Commands::TableToJson { input, output } => {
+        Commands::TableToJson { input, output, expand_tokens, token_separator } => {
             let output: PathBuf = output_path(output, &input, "json");
             let bytes = fs::read(&input)?;
-            let value: serde_json::Value = de::from_bytes(&bytes)?;
-            let s = serde_json::to_string_pretty(&value)?;
-            fs::write(&output, &s)?;
+            // the output is only written once the conversion has succeeded, as it may be the input
+            let mut json = Vec::new();
+            if expand_tokens.is_empty() {
+                // transcode straight into JSON, so entries keep their order and no JSON tree is built
+                let mut deserializer = cstc::Deserializer::with_options(&bytes, lenient_options());
+                serde_json::to_writer_pretty(&mut json, &Transcoder::new(&mut deserializer))?;
+                deserializer.end()?;
+            } else {
+                let table: HashTable = cstc::from_bytes_with_options(&bytes, lenient_options())?;
+                let options = TokenOptions::new(token_separator);
+                serde_json::to_writer_pretty(&mut json, &ExpandTokens { table: &table, keys: &expand_tokens, options })?;
+            }
+            fs::write(&output, json)?;
             std::eprintln!("Successfully converted \"{}\" to \"{}\"", input.to_string_lossy(), output.to_string_lossy())
         },
-        Commands::JsonToTable { input, output } => {
+        Commands::JsonToTable { input, output, sort_keys, nested_tables } => {
             let output: PathBuf = output_path(output, &input, "lvl");
-            let s = fs::read_to_string(&input)?;
-            let value: serde_json::Value = serde_json::from_str(&s)?;
-            let bytes = ser::to_bytes(&value)?;
-            fs::write(&output, &bytes)?;
+            let json = fs::read(&input)?;
+            let mut deserializer = serde_json::Deserializer::from_slice(&json);
+            let options = SerializerOptions { sort_keys: sort_keys.map(KeyOrder::from), nested_tables };
+            let bytes = cstc::to_bytes_with_options(&Transcoder::new(&mut deserializer), options)?;
+            deserializer.end()?;
+            fs::write(&output, bytes)?;
             std::eprintln!("Successfully converted \"{}\" to \"{}\"", input.to_string_lossy(), output.to_string_lossy())
         },
+        Commands::Fmt { input, output, order } => {
+            let output = output.unwrap_or_else(|| input.clone());
+            let bytes = fs::read(&input)?;
+            let table: HashTable = cstc::from_bytes_with_options(&bytes, lenient_options())?;
+            // nested tables in the input are written back as they were
+            let options = SerializerOptions { sort_keys: Some(order.into()), nested_tables: true };
+            let bytes = cstc::to_bytes_with_options(&table, options)?;
+            fs::write(&output, &bytes)?;
+            std::eprintln!("Successfully formatted \"{}\" to \"{}\"", input.to_string_lossy(), output.to_string_lossy())
+        },
     };
     Ok(())
 }
 
+/// Writes a table with the string values of `keys` split into arrays of tokens
+struct ExpandTokens<'a> {
+    table: &'a HashTable,
+    keys: &'a [String],
+    options: TokenOptions,
+}
+
+impl Serialize for ExpandTokens<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.table.len()))?;
+        for (key, value) in self.table.iter() {
+            match value {
+                Value::String(s) if self.keys.iter().any(|k| k == key) => map.serialize_entry(key, &self.options.split(s))?,
+                _ => map.serialize_entry(key, value)?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// Keep values with unknown type tags as raw bytes rather than refusing the whole file
+fn lenient_options() -> DeserializerOptions {
+    DeserializerOptions {
+        type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+        ..Default::default()
+    }
+}
+
 fn output_path(path: Option<PathBuf>, input_path: &Path, default_ext: &str) -> PathBuf {
     match path {
         Some(p) => p,
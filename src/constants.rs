@@ -1,3 +1,6 @@
+/// The header every Hash Table file starts with.
+pub const MAGIC: &[u8; 6] = b"MAP1.0";
+
 pub const TYPE_I64: u32 = 0;
 pub const TYPE_F64: u32 = 1;
-pub const TYPE_STRING: u32 = 2;
\ No newline at end of file
+pub const TYPE_STRING: u32 = 2;
@@ -0,0 +1,588 @@
+//! Converting between typed values and `HashTable` in memory, like `serde_json::to_value` and `from_value`.
+
+use alloc::string::String;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use serde::{forward_to_deserialize_any, ser, Serialize};
+
+use crate::constants::MAGIC;
+use crate::de::{parse_numeric_key, RawValueAccess};
+use crate::error::{ErrorKind, ErrorWithOffset};
+use crate::registry::TypeRegistry;
+use crate::ser::{encode_value, key_text, SerializerOptions};
+use crate::value::{HashTable, Value};
+
+type Result<T, E = ErrorWithOffset> = core::result::Result<T, E>;
+
+/// Serializes `value` into a `HashTable`, with each value getting the type tag `to_bytes` would write,
+/// and structs and maps in place of values becoming `Value::Table`.
+/// Errors have no offset, as there are no bytes to point into.
+pub fn to_value<T>(value: &T) -> Result<HashTable>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(TableSerializer).map_err(ErrorWithOffset::from)
+}
+
+/// Deserializes a `T` from the entries of `table`, the way `from_bytes` would from its bytes.
+pub fn from_value<T>(table: HashTable) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(table)
+}
+
+/// Serializes a struct or map into the entries of a table.
+struct TableSerializer;
+
+/// The entries serialized so far, and the key waiting for its value.
+struct TableEntries {
+    table: HashTable,
+    key: Option<String>,
+}
+
+impl TableEntries {
+    /// Goes through the encoded form of the value, so that the type tags are the ones `Serializer` chooses.
+    fn push_value<V>(&mut self, key: String, value: &V) -> Result<(), ErrorKind>
+    where
+        V: ?Sized + Serialize,
+    {
+        let options = SerializerOptions { nested_tables: true, ..Default::default() };
+        let encoded = encode_value(value, options)?;
+        let value = if encoded.starts_with(MAGIC) {
+            // a struct or map, whose own values get their type tags the same way
+            Value::Table(value.serialize(TableSerializer)?)
+        } else {
+            Value::from_encoded(&encoded, &TypeRegistry::new())
+        };
+        self.table.push(key, value);
+        Ok(())
+    }
+}
+
+impl ser::Serializer for TableSerializer {
+    type Ok = HashTable;
+    type Error = ErrorKind;
+    type SerializeSeq = ser::Impossible<HashTable, ErrorKind>;
+    type SerializeTuple = ser::Impossible<HashTable, ErrorKind>;
+    type SerializeTupleStruct = ser::Impossible<HashTable, ErrorKind>;
+    type SerializeTupleVariant = ser::Impossible<HashTable, ErrorKind>;
+    type SerializeMap = TableEntries;
+    type SerializeStruct = TableEntries;
+    type SerializeStructVariant = ser::Impossible<HashTable, ErrorKind>;
+
+    fn serialize_bool(self, _v: bool) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<HashTable, ErrorKind> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<HashTable, ErrorKind> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<HashTable, ErrorKind> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<HashTable, ErrorKind> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<HashTable, ErrorKind> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<HashTable, ErrorKind> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<HashTable, ErrorKind> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_none(self) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<HashTable, ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<HashTable, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<HashTable, ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<HashTable, ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<TableEntries, ErrorKind> {
+        Ok(TableEntries { table: HashTable::new(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<TableEntries, ErrorKind> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, ErrorKind> {
+        Err(ErrorKind::UnsupportedValue)
+    }
+}
+
+impl ser::SerializeMap for TableEntries {
+    type Ok = HashTable;
+    type Error = ErrorKind;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.key = Some(key_text(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self.key.take().ok_or(ErrorKind::UnsupportedValue)?;
+        self.push_value(key, value)
+    }
+
+    fn end(self) -> Result<HashTable, ErrorKind> {
+        Ok(self.table)
+    }
+}
+
+impl ser::SerializeStruct for TableEntries {
+    type Ok = HashTable;
+    type Error = ErrorKind;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), ErrorKind>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push_value(key_text(key)?, value)
+    }
+
+    fn end(self) -> Result<HashTable, ErrorKind> {
+        Ok(self.table)
+    }
+}
+
+impl Value {
+    fn into_integer<T>(self) -> Result<T>
+    where
+        T: TryFrom<i64>,
+    {
+        match self {
+            Value::Int(v) => T::try_from(v).or(Err(ErrorKind::NumericOverflow.into())),
+            _ => Err(ErrorKind::TypeMismatch.into()),
+        }
+    }
+
+    fn into_float(self) -> Result<f64> {
+        match self {
+            Value::Float(v) => Ok(v),
+            _ => Err(ErrorKind::TypeMismatch.into()),
+        }
+    }
+
+    fn into_string(self) -> Result<String> {
+        match self {
+            Value::String(v) => Ok(v),
+            _ => Err(ErrorKind::TypeMismatch.into()),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ErrorWithOffset> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ErrorWithOffset> for HashTable {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserializes a value the way `Deserializer` reads it from a table: each Rust type accepts only its own type tag.
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = ErrorWithOffset;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        match self {
+            Value::Int(v) => visitor.visit_i64(v),
+            Value::Float(v) => visitor.visit_f64(v),
+            Value::String(v) => visitor.visit_string(v),
+            Value::Raw(raw) => visitor.visit_map(RawValueAccess { type_id: raw.type_id, payload: raw.payload, next_entry: 0 }),
+            Value::Table(table) => table.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_bool(self.into_integer::<i64>()? != 0)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_i8(self.into_integer()?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_i16(self.into_integer()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_i32(self.into_integer()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_i64(self.into_integer()?)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_u8(self.into_integer()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_u16(self.into_integer()?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_u32(self.into_integer()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_u64(self.into_integer()?)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_f32(self.into_float()? as f32)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_f64(self.into_float()?)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        let s = self.into_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(ErrorKind::TypeMismatch.into()),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_string(self.into_string()?)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_string(self.into_string()?)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.deserialize_str(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf unit unit_struct seq tuple tuple_struct map struct enum ignored_any
+    }
+}
+
+/// Presents the entries of a table as a map, like `Deserializer` does for its input.
+impl<'de> de::Deserializer<'de> for HashTable {
+    type Error = ErrorWithOffset;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        let len = self.len();
+        visitor.visit_map(TableAccess { entries: self.into_iter(), len, value: None })
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit unit_struct
+        seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct TableAccess {
+    entries: alloc::vec::IntoIter<(String, Value)>,
+    len: usize,
+    /// The value of the last key handed out, and the key for errors in it.
+    value: Option<(String, Value)>,
+}
+
+impl<'de> MapAccess<'de> for TableAccess {
+    type Error = ErrorWithOffset;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let result = seed.deserialize(KeyDeserializer(key.clone()));
+        self.value = Some((key, value));
+        result.map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let (key, value) = self.value.take().ok_or_else(|| <ErrorWithOffset as de::Error>::custom("value is missing"))?;
+        seed.deserialize(value).map_err(|e| e.for_key(&key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// A key, which is text unless an integer is asked for, as with `Deserializer`.
+struct KeyDeserializer(String);
+
+impl KeyDeserializer {
+    fn into_integer(self) -> Result<Value> {
+        Ok(Value::Int(parse_numeric_key(self.0)?))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = ErrorWithOffset;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_bool(visitor)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        self.into_integer()?.deserialize_u64(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
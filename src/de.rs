@@ -1,39 +1,181 @@
-use serde::{de::{self, DeserializeSeed, MapAccess, Visitor}, Deserialize};
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ops::Range;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{de::{self, value::MapDeserializer, DeserializeSeed, IntoDeserializer, MapAccess, Visitor}, forward_to_deserialize_any, Deserialize};
 use encoding_rs::{self, WINDOWS_1252};
 
 use crate::constants::*;
+use crate::registry::{PayloadLength, TypeRegistry};
+use crate::spanned;
+use crate::value::{RAW_PAYLOAD_KEY, RAW_TYPE_KEY};
 
-use crate::error::{ErrorKind as ErrKind, ErrorWithOffset};
+use crate::error::{ErrorKind as ErrKind, ErrorWithOffset, Limit};
 
-type Result<T> = std::result::Result<T, ErrorWithOffset>;
+type Result<T> = core::result::Result<T, ErrorWithOffset>;
 
+#[derive(Clone)]
 pub struct Deserializer<'de> {
     input: &'de [u8],
     start_len: usize,
     reading_value: bool,
     reading_key: bool,
+    /// Where the key of the value being read is, for `Spanned`.
+    key_range: Range<usize>,
+    /// Entries read so far, in all tables.
+    entries_read: usize,
+    /// How many tables are being read, one inside the other.
+    depth: usize,
+    options: DeserializerOptions,
+    defaults: Defaults,
+}
+
+/// Which struct fields get a Construct default, as learned over passes of `read_with_defaults`.
+#[derive(Debug, Clone, Default)]
+struct Defaults {
+    /// `(struct, field)` names that a struct refused a default for, as they're aliases of a field it
+    /// had already read. `FIELDS` lists aliases like any other field, so only this tells them apart.
+    aliases: Vec<(&'static str, &'static str)>,
+    /// The field a default was last handed out for, until its value is read.
+    pending: Cell<Option<(&'static str, &'static str)>>,
+}
+
+impl Defaults {
+    fn is_alias(&self, name: &str, field: &str) -> bool {
+        self.aliases.iter().any(|&(n, f)| n == name && f == field)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeserializerOptions {
+    /// Struct fields missing from the table deserialize to `0` or `""` (depending on the requested type),
+    /// the same way Construct's Hash Table answers lookups of keys that don't exist.
+    /// A missing struct field is read as a struct of such defaults, and a missing map as an empty one.
+    /// This takes precedence over `#[serde(default)]` on the missing fields. A field read under one of its
+    /// `#[serde(alias)]` names gets no default under the others, which takes another pass over the table
+    /// for each such alias.
+    pub construct_defaults: bool,
+    /// Type tags to accept besides the built-in ones. Their values are presented as
+    /// `{"$type": N, "$raw": "<base64>"}` maps, which `Value` and `RawValue` deserialize from.
+    pub type_registry: TypeRegistry,
+    /// Bounds on the input, for reading tables from untrusted sources.
+    pub limits: Limits,
+}
+
+/// Bounds on what a table may contain. Each one left at `None` is not checked, which is the default.
+/// Going past a bound fails with `ErrorKind::LimitExceeded` at the offset where it happened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// How many entries may be read, counting those of nested tables.
+    pub max_entries: Option<usize>,
+    /// The longest key, in stored bytes without the NUL terminator.
+    pub max_key_len: Option<usize>,
+    /// The longest string value, in stored bytes without the NUL terminator. This also bounds the byte count
+    /// at the start of `PayloadLength::Prefixed` payloads.
+    pub max_string_len: Option<usize>,
+    /// How many tables may be nested, counting the outermost one. A struct or map field is stored as
+    /// a table in place of the value, one level deeper.
+    pub max_depth: Option<usize>,
+    /// How many bytes of input may be read in total. This also bounds how much memory the values need,
+    /// and how much of a stream `from_async_reader` reads.
+    pub max_total_bytes: Option<usize>,
+}
+
+fn check_limit(limit: Limit, max: Option<usize>, value: usize, offset: usize) -> Result<()> {
+    match max {
+        Some(max) if value > max => ErrKind::LimitExceeded(limit, max).with(offset),
+        _ => Ok(()),
+    }
+}
+
+impl Limits {
+    /// Checks the length prefix of a key or string value at `offset`, which counts the NUL terminator.
+    pub(crate) fn check_str_len(&self, key: bool, len: u32, offset: usize) -> Result<()> {
+        let (limit, max) = if key { (Limit::KeyLength, self.max_key_len) } else { (Limit::StringLength, self.max_string_len) };
+        check_limit(limit, max, (len as usize).saturating_sub(1), offset)
+    }
+
+    /// Checks the length prefix of a `PayloadLength::Prefixed` payload at `offset`.
+    pub(crate) fn check_payload_len(&self, len: u32, offset: usize) -> Result<()> {
+        check_limit(Limit::StringLength, self.max_string_len, len as usize, offset)
+    }
+}
+
+/// How a value is laid out, which says where it ends. The deserializer and `from_async_reader`
+/// both go by this, so that they agree on it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Framing {
+    /// A nested table, whose `MAP1.0` header takes the place of the type tag.
+    Table,
+    /// An integer or float: the type tag and 8 bytes.
+    Number,
+    /// The type tag, a `u32` length counting the NUL terminator, and the string.
+    String,
+    /// The type tag and a payload of a registered type.
+    Raw(PayloadLength),
+    /// A type tag that isn't registered, so the value can't be read.
+    Unknown,
+}
+
+impl Framing {
+    /// The framing of the value `head` starts with, of which the first 6 bytes are enough to tell.
+    /// Returns `None` if `head` is shorter than a type tag.
+    pub(crate) fn of(head: &[u8], registry: &TypeRegistry) -> Option<Framing> {
+        if head.starts_with(MAGIC) {
+            return Some(Framing::Table);
+        }
+        let type_id = u32::from_le_bytes(head.get(..4)?.try_into().unwrap());
+        Some(match type_id {
+            TYPE_I64 | TYPE_F64 => Framing::Number,
+            TYPE_STRING => Framing::String,
+            _ => registry.get(type_id).map_or(Framing::Unknown, Framing::Raw),
+        })
+    }
 }
 
 pub fn from_bytes<'a, T>(b: &'a [u8]) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_bytes(b);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.is_empty() {
-        Ok(t)
-    } else {
-        ErrKind::TrailingCharacters.with(deserializer.offset())
-    }
+    from_bytes_with_options(b, DeserializerOptions::default())
+}
+
+pub fn from_bytes_with_options<'a, T>(b: &'a [u8], options: DeserializerOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::with_options(b, options);
+    let t = deserializer.read_with_defaults(|de| T::deserialize(de))?;
+    deserializer.end()?;
+    Ok(t)
 }
 
 struct KeyValueList<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    /// The name of the struct being read, for telling its aliases apart.
+    name: &'static str,
+    /// Struct fields that haven't been seen in the table yet (only tracked with `construct_defaults`).
+    missing_fields: Vec<&'static str>,
+    /// The last key was a missing field, so its value is a Construct default rather than input.
+    default_value: bool,
+    /// The last key read from the input, for errors in its value.
+    key: &'de [u8],
+    /// Entries left in a table stored in place of a value, which ends after the key count in its header.
+    /// The outermost table runs to the end of the input instead.
+    remaining: Option<u32>,
 }
 
 impl<'a, 'de> KeyValueList<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
-        KeyValueList { de }
+    fn new(de: &'a mut Deserializer<'de>, name: &'static str, fields: &'static [&'static str], remaining: Option<u32>) -> Self {
+        let missing_fields = if de.options.construct_defaults {
+            fields.iter().copied().filter(|field| !de.defaults.is_alias(name, field)).collect()
+        } else {
+            Vec::new()
+        };
+        KeyValueList { de, name, missing_fields, default_value: false, key: &[], remaining }
     }
 }
 
@@ -45,12 +187,36 @@ impl<'de, 'a> MapAccess<'de> for KeyValueList<'a, 'de> {
         K: DeserializeSeed<'de>,
     {
         // Check if there are no more entries.
-        if self.de.input.len() == 0 {
-            return Ok(None);
+        let at_end = match self.remaining {
+            Some(remaining) => remaining == 0,
+            None => self.de.input.is_empty(),
+        };
+        if at_end {
+            // Once the table is exhausted, hand out the fields it didn't contain.
+            if self.missing_fields.is_empty() {
+                return Ok(None);
+            }
+            let field = self.missing_fields.remove(0);
+            self.default_value = true;
+            self.de.defaults.pending.set(Some((self.name, field)));
+            return seed.deserialize(de::value::BorrowedStrDeserializer::new(field)).map(Some);
         }
+        self.de.begin_entry()?;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= 1;
+        }
+        self.key = self.de.peek_str_bytes().unwrap_or_default();
         self.de.reading_key = true;
+        if !self.missing_fields.is_empty() {
+            let key = self.de.peek_string();
+            if let Ok(key) = key {
+                self.missing_fields.retain(|field| *field != key);
+            }
+        }
+        let key_start = self.de.offset();
         let result = seed.deserialize(&mut *self.de).map(Some);
         self.de.reading_key = false;
+        self.de.key_range = key_start..self.de.offset();
         result
     }
 
@@ -58,45 +224,65 @@ impl<'de, 'a> MapAccess<'de> for KeyValueList<'a, 'de> {
     where
         V: DeserializeSeed<'de>,
     {
+        if self.default_value {
+            self.default_value = false;
+            self.de.defaults.pending.set(None);
+            return seed.deserialize(ConstructDefault { defaults: &self.de.defaults });
+        }
         // Deserialize a map value.
+        let value_offset = self.de.offset();
         self.de.reading_value = true;
         let result = seed.deserialize(&mut *self.de);
         self.de.reading_value = false;
-        result
+        result.map_err(|e| e.at_entry(&WINDOWS_1252.decode_without_bom_handling(self.key).0, value_offset))
     }
 }
 
 impl<'de> Deserializer<'de> {
     fn offset(&self) -> usize {
-        return self.start_len - self.input.len();
+        self.start_len - self.input.len()
     }
 
-    fn peek_u32(&mut self) -> u32 {
-        u32::from_le_bytes(self.input[..4].try_into().unwrap())
+    fn take(&mut self, len: usize) -> Result<&'de [u8]> {
+        if len > self.input.len() {
+            return ErrKind::UnexpectedEnd.with(self.offset());
+        }
+        check_limit(Limit::TotalBytes, self.options.limits.max_total_bytes, self.offset() + len, self.offset())?;
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    fn peek_u32(&mut self) -> Result<u32> {
+        match self.input.get(..4) {
+            Some(bytes) => Ok(u32::from_le_bytes(bytes.try_into().unwrap())),
+            None => ErrKind::UnexpectedEnd.with(self.offset()),
+        }
     }
 
-    fn read_u32(&mut self) -> u32 {
-        let v = u32::from_le_bytes(self.input[..4].try_into().unwrap());
-        self.input = &self.input[4..];
-        v
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Checks the type tag of a value against the requested type.
+    fn read_tag(&mut self, expected: u32) -> Result<()> {
+        if self.reading_value && self.read_u32()? != expected {
+            return ErrKind::TypeMismatch.with(self.offset());
+        }
+        Ok(())
     }
 
     fn read_i64(&mut self) -> Result<i64> {
-        if self.reading_value {
-            if self.read_u32() != TYPE_I64 { return ErrKind::TypeMismatch.with(self.offset()); }
+        if self.reading_key {
+            return self.read_numeric_key();
         }
-        let v = i64::from_le_bytes(self.input[..8].try_into().unwrap());
-        self.input = &self.input[8..];
-        Ok(v)
+        self.read_tag(TYPE_I64)?;
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
     }
 
     fn read_f64(&mut self) -> Result<f64> {
-        if self.reading_value {
-            if self.read_u32() != TYPE_F64 { return ErrKind::TypeMismatch.with(self.offset()); }
-        }
-        let v = f64::from_le_bytes(self.input[..8].try_into().unwrap());
-        self.input = &self.input[8..];
-        Ok(v)
+        self.read_tag(TYPE_F64)?;
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
     }
 
     fn read_integer<T>(&mut self) -> Result<T>
@@ -106,31 +292,441 @@ impl<'de> Deserializer<'de> {
         result.or(ErrKind::NumericOverflow.with(self.offset()))
     }
 
-    pub fn read_string(&mut self) -> Result<String> {
-        if self.reading_value {
-            if self.read_u32() != TYPE_STRING { return ErrKind::TypeMismatch.with(self.offset()); }
-        }
-        let len = self.read_u32() as usize;
+    /// Reads a length-prefixed string, returning its bytes without the terminating NUL.
+    fn read_str_bytes(&mut self) -> Result<&'de [u8]> {
+        let len = self.read_u32()?;
+        self.options.limits.check_str_len(self.reading_key, len, self.offset() - 4)?;
+        let len = len as usize;
         if len > self.input.len() {
             return ErrKind::StringLengthError(len, self.input.len()).with(self.offset()-4);
         }
-        if self.input[len-1] != 0 {
+        if len == 0 || self.input[len-1] != 0 {
             // all strings end with a NUL
-            return ErrKind::MissingStringTerminator.with(self.offset()+len-1);
+            return ErrKind::MissingStringTerminator.with(self.offset()+len.saturating_sub(1));
+        }
+        let bytes = self.take(len)?;
+        Ok(&bytes[..len-1])
+    }
+
+    pub fn read_string(&mut self) -> Result<String> {
+        self.read_tag(TYPE_STRING)?;
+        let bytes = self.read_str_bytes()?;
+        let (s, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+        Ok(s.into_owned())
+    }
+
+    /// Reads the type tag and payload of a value whose tag has no built-in decoding.
+    fn read_raw(&mut self) -> Result<(u32, &'de [u8])> {
+        let tag_offset = self.offset();
+        let framing = Framing::of(self.input, &self.options.type_registry);
+        let type_id = self.read_u32()?;
+        let Some(Framing::Raw(length)) = framing else {
+            return ErrKind::UnknownTypeId(type_id).with(tag_offset);
+        };
+        if matches!(length, PayloadLength::Prefixed) {
+            let len = self.peek_u32()?;
+            self.options.limits.check_payload_len(len, self.offset())?;
+        }
+        let Some(len) = self.options.type_registry.payload_len(length, self.input) else {
+            return ErrKind::InvalidPayload(type_id).with(self.offset());
+        };
+        Ok((type_id, self.take(len)?))
+    }
+
+    /// Moves past a value without decoding it.
+    fn skip_value(&mut self) -> Result<()> {
+        match Framing::of(self.input, &self.options.type_registry) {
+            Some(Framing::Table) => return self.skip_table(),
+            Some(Framing::Number) => {
+                self.take(12)?;
+            },
+            Some(Framing::String) => {
+                self.take(4)?;
+                self.read_str_bytes()?;
+            },
+            Some(Framing::Raw(_) | Framing::Unknown) => {
+                self.read_raw()?;
+            },
+            None => return ErrKind::UnexpectedEnd.with(self.offset()),
+        }
+        Ok(())
+    }
+
+    /// Reads a key holding the decimal text of an integer.
+    fn read_numeric_key(&mut self) -> Result<i64> {
+        let offset = self.offset();
+        let key = self.read_string()?;
+        parse_numeric_key(key).map_err(|kind| ErrorWithOffset::new(offset, kind))
+    }
+
+    fn read_char(&mut self) -> Result<char> {
+        let offset = self.offset();
+        let s = self.read_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => ErrKind::TypeMismatch.with(offset),
+        }
+    }
+
+    fn peek_str_bytes(&mut self) -> Result<&'de [u8]> {
+        let input = self.input;
+        let result = self.read_str_bytes();
+        self.input = input;
+        result
+    }
+
+    fn peek_string(&mut self) -> Result<String> {
+        let input = self.input;
+        let result = self.read_string();
+        self.input = input;
+        result
+    }
+
+    /// Reads the `MAP1.0` header, returning the key count it declares.
+    /// The table counts towards `Limits::max_depth` until the caller leaves it.
+    pub(crate) fn read_header(&mut self) -> Result<u32> {
+        check_limit(Limit::Depth, self.options.limits.max_depth, self.depth + 1, self.offset())?;
+        if !self.input.starts_with(MAGIC) {
+            return ErrKind::InvalidHeader.with(self.offset());
+        }
+        self.input = &self.input[MAGIC.len()..];
+        self.depth += 1;
+        self.read_u32()
+    }
+
+    /// Moves past a table stored in place of a value.
+    fn skip_table(&mut self) -> Result<()> {
+        let key_count = self.read_header()?;
+        for _ in 0..key_count {
+            self.begin_entry()?;
+            self.reading_key = true;
+            let key = self.read_str_bytes();
+            self.reading_key = false;
+            key?;
+            self.skip_value()?;
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Counts an entry about to be read against `Limits::max_entries`.
+    fn begin_entry(&mut self) -> Result<()> {
+        self.entries_read += 1;
+        check_limit(Limit::Entries, self.options.limits.max_entries, self.entries_read, self.offset())
+    }
+
+    /// Reads the next entry without decoding its value, returning `None` at the end of the table.
+    pub(crate) fn read_entry_span(&mut self) -> Result<Option<EntrySpan<'de>>> {
+        if self.input.is_empty() {
+            return Ok(None);
         }
-        let slice = &self.input[..len-1]; // exclude the terminating NUL byte
-        let (s, _, _) = WINDOWS_1252.decode(slice);
-        self.input = &self.input[len..];
-        Ok(s.to_string())
+        self.begin_entry()?;
+        let key_start = self.offset();
+        self.reading_key = true;
+        let key = self.read_str_bytes();
+        self.reading_key = false;
+        let key = key?;
+        let value_start = self.offset();
+        self.skip_value()?;
+        Ok(Some(EntrySpan { key, key_range: key_start..value_start, value_range: value_start..self.offset() }))
+    }
+
+    /// Presents the value about to be read as a `Spanned`, with where its key and value are.
+    fn deserialize_spanned<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        let value_start = self.offset();
+        let (input, entries_read) = (self.input, self.entries_read);
+        self.skip_value()?;
+        let positions = [self.key_range.start, self.key_range.end, value_start, self.offset()];
+        (self.input, self.entries_read) = (input, entries_read);
+        visitor.visit_map(SpannedAccess { value: Some(self), positions, next_field: 0 })
+    }
+
+    fn deserialize_table<V>(&mut self, name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        let nested = self.reading_value;
+        if nested && !self.input.starts_with(MAGIC) {
+            return ErrKind::TypeMismatch.with(self.offset());
+        }
+        let key_count = self.read_header()?;
+        self.reading_value = false;
+        let value = visitor.visit_map(KeyValueList::new(self, name, fields, nested.then_some(key_count)));
+        self.reading_value = nested;
+        self.depth -= 1;
+        value
     }
 
     pub fn from_bytes(input: &'de [u8]) -> Self {
-        Self { start_len: input.len(), input, reading_value: false, reading_key: false }
+        Self::with_options(input, DeserializerOptions::default())
+    }
+
+    pub fn with_options(input: &'de [u8], options: DeserializerOptions) -> Self {
+        Self { start_len: input.len(), input, reading_value: false, reading_key: false, key_range: 0..0, entries_read: 0, depth: 0, options, defaults: Defaults::default() }
+    }
+
+    /// Runs `read`, and runs it again from the start for as long as it fails because a struct refused
+    /// a Construct default for an alias of a field it had already read, leaving that alias out the next time.
+    pub(crate) fn read_with_defaults<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T>) -> Result<T> {
+        if !self.options.construct_defaults {
+            return read(self);
+        }
+        let start = self.clone();
+        loop {
+            let result = read(self);
+            match self.defaults.pending.take() {
+                Some(alias) if result.is_err() => {
+                    let mut aliases = core::mem::take(&mut self.defaults.aliases);
+                    aliases.push(alias);
+                    *self = start.clone();
+                    self.defaults.aliases = aliases;
+                },
+                _ => return result,
+            }
+        }
+    }
+
+    /// Checks that the whole input has been consumed, for use after deserializing a table
+    /// directly from a `Deserializer`.
+    pub fn end(&self) -> Result<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            ErrKind::TrailingCharacters.with(self.offset())
+        }
+    }
+
+    /// A deserializer for the single value starting at `offset` in `table`, whose key is at `key_range`.
+    pub(crate) fn for_value(table: &'de [u8], key_range: Range<usize>, offset: usize, options: DeserializerOptions) -> Self {
+        let input = &table[offset..];
+        Self { start_len: table.len(), input, reading_value: true, reading_key: false, key_range, entries_read: 0, depth: 0, options, defaults: Defaults::default() }
+    }
+}
+
+/// Parses a key holding the decimal text of an integer.
+pub(crate) fn parse_numeric_key(key: String) -> core::result::Result<i64, ErrKind> {
+    match key.parse::<i64>() {
+        Ok(v) => Ok(v),
+        Err(_) if key.parse::<i128>().is_ok() => Err(ErrKind::NumericOverflow),
+        Err(_) => Err(ErrKind::NonNumericKey(key)),
     }
 }
 
+/// Where an entry is in the input, as found by `Deserializer::read_entry_span`.
+pub(crate) struct EntrySpan<'de> {
+    /// The key as stored, without its length prefix and NUL terminator.
+    pub key: &'de [u8],
+    /// The key's length prefix, text and NUL terminator.
+    pub key_range: Range<usize>,
+    /// The value's type tag and payload.
+    pub value_range: Range<usize>,
+}
+
+/// Presents a value with a non-standard type tag as `{"$type": N, "$raw": "<base64>"}`.
+pub(crate) struct RawValueAccess<P> {
+    pub type_id: u32,
+    pub payload: P,
+    pub next_entry: usize,
+}
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de, P: AsRef<[u8]>> MapAccess<'de> for RawValueAccess<P> {
+    type Error = ErrorWithOffset;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let key = match self.next_entry {
+            0 => RAW_TYPE_KEY,
+            1 => RAW_PAYLOAD_KEY,
+            _ => return Ok(None),
+        };
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.next_entry += 1;
+        if self.next_entry == 1 {
+            seed.deserialize(de::value::U32Deserializer::new(self.type_id))
+        } else {
+            seed.deserialize(de::value::StringDeserializer::new(BASE64.encode(self.payload.as_ref())))
+        }
+    }
+}
+
+/// Presents a value as the fields of a `Spanned`: the start and end of its key and of itself, then the value.
+struct SpannedAccess<D> {
+    /// Deserializes the value, once the positions have been handed out.
+    value: Option<D>,
+    /// The start and end of the key, then of the value.
+    positions: [usize; 4],
+    next_field: usize,
+}
+
+impl<'de, D> MapAccess<'de> for SpannedAccess<D>
+where
+    D: de::Deserializer<'de, Error = ErrorWithOffset>,
+{
+    type Error = ErrorWithOffset;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match spanned::FIELDS.get(self.next_field) {
+            Some(field) => seed.deserialize(de::value::BorrowedStrDeserializer::new(field)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let field = self.next_field;
+        self.next_field += 1;
+        match self.positions.get(field) {
+            Some(&position) => seed.deserialize(de::value::U64Deserializer::new(position as u64)),
+            None => match self.value.take() {
+                Some(value) => seed.deserialize(value),
+                None => Err(de::Error::custom("a Spanned value can only be read once")),
+            },
+        }
+    }
+}
+
+/// Stands in for the value of a struct field that is missing from the table,
+/// answering with whatever Construct's Hash Table returns for an unknown key.
+#[derive(Clone, Copy)]
+struct ConstructDefault<'a> {
+    defaults: &'a Defaults,
+}
+
+impl<'de> de::Deserializer<'de> for ConstructDefault<'_> {
+    type Error = ErrorWithOffset;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_i64(0)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_f32(0.0)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_borrowed_str("")
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_string(String::new())
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        visitor.visit_map(MapDeserializer::new(core::iter::empty::<(&str, Self)>()))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de> {
+        if name == spanned::NAME {
+            // there is nothing in the input to point at
+            return visitor.visit_map(SpannedAccess { value: Some(self), positions: [0; 4], next_field: 0 });
+        }
+        visitor.visit_map(DefaultFields { defaults: self.defaults, name, fields: fields.iter() })
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 char bytes byte_buf unit unit_struct
+        seq tuple tuple_struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, ErrorWithOffset> for ConstructDefault<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The fields of a struct that is missing altogether, each with a Construct default.
+struct DefaultFields<'a> {
+    defaults: &'a Defaults,
+    name: &'static str,
+    fields: core::slice::Iter<'static, &'static str>,
+}
+
+impl<'de> MapAccess<'de> for DefaultFields<'_> {
+    type Error = ErrorWithOffset;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some(&field) = self.fields.find(|field| !self.defaults.is_alias(self.name, field)) else { return Ok(None) };
+        self.defaults.pending.set(Some((self.name, field)));
+        seed.deserialize(de::value::BorrowedStrDeserializer::new(field)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.defaults.pending.set(None);
+        seed.deserialize(ConstructDefault { defaults: self.defaults })
+    }
+}
+
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = ErrorWithOffset;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
@@ -139,11 +735,17 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         if self.reading_key {
             self.deserialize_str(visitor)
         } else if self.reading_value {
-            match self.peek_u32() {
+            if self.input.starts_with(MAGIC) {
+                return self.deserialize_table("", &[], visitor);
+            }
+            match self.peek_u32()? {
                 TYPE_I64 => { self.deserialize_i64(visitor) },
                 TYPE_F64 => { self.deserialize_f64(visitor) },
                 TYPE_STRING => { self.deserialize_str(visitor) },
-                ty => { ErrKind::UnknownTypeId(ty).with(self.offset()) }
+                _ => {
+                    let (type_id, payload) = self.read_raw()?;
+                    visitor.visit_map(RawValueAccess { type_id, payload, next_entry: 0 })
+                },
             }
         } else {
             self.deserialize_map(visitor)
@@ -165,7 +767,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        self.deserialize_string(visitor)
+        self.read_tag(TYPE_STRING)?;
+        let bytes = self.read_str_bytes()?;
+        // ASCII text reads the same in Windows-1252, so it can be borrowed from the input
+        match WINDOWS_1252.decode_without_bom_handling(bytes).0 {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -207,7 +815,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        visitor.visit_u8(self.read_integer()?)
+        visitor.visit_u16(self.read_integer()?)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
@@ -228,10 +836,10 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_f32(self.read_f64()? as f32)
     }
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        unimplemented!()
+        visitor.visit_char(self.read_char()?)
     }
 
     fn deserialize_bytes<V>(self, _visitor: V) -> Result<V::Value>
@@ -246,10 +854,11 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         unimplemented!()
     }
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        unimplemented!()
+        // a table has no null value, so an entry that is there is always `Some`
+        visitor.visit_some(self)
     }
 
     fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value>
@@ -304,32 +913,25 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        // magic number: MAP1.0
-        if !(
-            self.input[0] == 0x4D
-            && self.input[1] == 0x41
-            && self.input[2] == 0x50
-            && self.input[3] == 0x31
-            && self.input[4] == 0x2E
-            && self.input[5] == 0x30
-        ) {
-            return ErrKind::InvalidHeader.with(self.offset());
+        if self.reading_value {
+            // Maps inside a table are nested tables or values with non-standard type tags.
+            return self.deserialize_any(visitor);
         }
-        self.input = &self.input[6..];
-        let _key_count = self.read_u32();
-        let value = visitor.visit_map(KeyValueList::new(self))?;
-        Ok(value)
+        self.deserialize_table("", &[], visitor)
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de> {
-        self.deserialize_map(visitor)
+        if self.reading_value && name == spanned::NAME {
+            return self.deserialize_spanned(visitor);
+        }
+        self.deserialize_table(name, fields, visitor)
     }
 
     fn deserialize_enum<V>(
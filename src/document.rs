@@ -0,0 +1,177 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use encoding_rs::WINDOWS_1252;
+
+use crate::constants::*;
+use crate::de::{Deserializer, DeserializerOptions};
+use crate::error::{ErrorKind, ErrorWithOffset};
+use crate::registry::TypeRegistry;
+use crate::ser::{encode_key, encode_value, SerializerOptions};
+use crate::value::Value;
+
+/// A Hash Table file that can be edited by key while keeping the original bytes of
+/// everything that wasn't edited: entries stay in order, keys keep their exact encoding,
+/// and duplicate keys and values with unknown type tags are carried over untouched.
+///
+/// Lookups and edits apply to the first entry with a given key. Nested tables are read as `Value::Table`,
+/// but can't be written, as Construct itself can't read them.
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    source: Vec<u8>,
+    entries: Vec<Entry>,
+    /// The registry the source was parsed with, for decoding the values of nested tables.
+    type_registry: TypeRegistry,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    key_bytes: Bytes,
+    value_bytes: Bytes,
+}
+
+/// Encoded bytes, either still in the source buffer or produced by an edit.
+#[derive(Debug, Clone)]
+enum Bytes {
+    Source(Range<usize>),
+    Edited(Vec<u8>),
+}
+
+impl Document {
+    /// An empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(bytes: impl Into<Vec<u8>>) -> Result<Self, ErrorWithOffset> {
+        Self::parse_with_options(bytes, DeserializerOptions::default())
+    }
+
+    /// Parses with the given options, of which only the type registry and limits are relevant.
+    pub fn parse_with_options(bytes: impl Into<Vec<u8>>, options: DeserializerOptions) -> Result<Self, ErrorWithOffset> {
+        let source = bytes.into();
+        let type_registry = options.type_registry.clone();
+        let mut deserializer = Deserializer::with_options(&source, options);
+        let _key_count = deserializer.read_header()?;
+        let mut entries = Vec::new();
+        while let Some(span) = deserializer.read_entry_span()? {
+            let (key, _) = WINDOWS_1252.decode_without_bom_handling(span.key);
+            entries.push(Entry {
+                key: key.into_owned(),
+                key_bytes: Bytes::Source(span.key_range),
+                value_bytes: Bytes::Source(span.value_range),
+            });
+        }
+        Ok(Document { source, entries, type_registry })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.key.as_str())
+    }
+
+    /// The entries in file order, including duplicate keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.entries.iter().map(|entry| (entry.key.as_str(), Value::from_encoded(self.bytes(&entry.value_bytes), &self.type_registry)))
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entry = &self.entries[self.position(key)?];
+        Some(Value::from_encoded(self.bytes(&entry.value_bytes), &self.type_registry))
+    }
+
+    /// Replaces the value of `key` in place, or appends a new entry if the key isn't present.
+    /// Returns the previous value.
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) -> Result<Option<Value>, ErrorKind> {
+        let value_bytes = Bytes::Edited(encode_value(&value.into(), SerializerOptions::default())?);
+        match self.position(key) {
+            Some(index) => {
+                let previous = self.get(key);
+                self.entries[index].value_bytes = value_bytes;
+                Ok(previous)
+            },
+            None => {
+                self.push(key, value_bytes)?;
+                Ok(None)
+            },
+        }
+    }
+
+    /// Appends a new entry, unless `key` is already present. Returns whether the entry was added.
+    pub fn insert(&mut self, key: &str, value: impl Into<Value>) -> Result<bool, ErrorKind> {
+        if self.contains_key(key) {
+            return Ok(false);
+        }
+        let value_bytes = Bytes::Edited(encode_value(&value.into(), SerializerOptions::default())?);
+        self.push(key, value_bytes)?;
+        Ok(true)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let previous = self.get(key)?;
+        self.entries.remove(self.position(key)?);
+        Some(previous)
+    }
+
+    /// Re-emits the table, with the header's key count updated to the current number of entries.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.source.len());
+        output.extend_from_slice(MAGIC);
+        output.extend_from_slice(&self.key_count().expect("too many entries for a table").to_le_bytes());
+        for entry in &self.entries {
+            output.extend_from_slice(self.bytes(&entry.key_bytes));
+            output.extend_from_slice(self.bytes(&entry.value_bytes));
+        }
+        output
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let key_count = self.key_count()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, ErrorKind::NumericOverflow))?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&key_count.to_le_bytes())?;
+        for entry in &self.entries {
+            writer.write_all(self.bytes(&entry.key_bytes))?;
+            writer.write_all(self.bytes(&entry.value_bytes))?;
+        }
+        Ok(())
+    }
+
+    fn key_count(&self) -> Option<u32> {
+        u32::try_from(self.entries.len()).ok()
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.key == key)
+    }
+
+    fn push(&mut self, key: &str, value_bytes: Bytes) -> Result<(), ErrorKind> {
+        let key_bytes = Bytes::Edited(encode_key(key)?);
+        self.entries.push(Entry { key: key.to_owned(), key_bytes, value_bytes });
+        Ok(())
+    }
+
+    fn bytes<'a>(&'a self, bytes: &'a Bytes) -> &'a [u8] {
+        match bytes {
+            Bytes::Source(range) => &self.source[range.clone()],
+            Bytes::Edited(bytes) => bytes,
+        }
+    }
+}
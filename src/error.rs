@@ -1,16 +1,45 @@
-use std::fmt::{self, Display};
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
 
 use serde::{de, ser};
 
 #[derive(Debug)]
 pub struct ErrorWithOffset {
     offset: Option<usize>,
+    key: Option<String>,
     kind: ErrorKind,
 }
 
 impl ErrorWithOffset {
     pub fn new(offset: usize, kind: ErrorKind) -> Self {
-        ErrorWithOffset { offset: Some(offset), kind }
+        ErrorWithOffset { offset: Some(offset), key: None, kind }
+    }
+
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// The key of the entry whose value couldn't be deserialized.
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Fills in where the error happened, unless a more precise location is already known.
+    pub(crate) fn at_entry(mut self, key: &str, value_offset: usize) -> Self {
+        self.offset.get_or_insert(value_offset);
+        self.for_key(key)
+    }
+
+    /// Fills in the key of the entry the error happened in, unless it is already known.
+    pub(crate) fn for_key(mut self, key: &str) -> Self {
+        if self.key.is_none() {
+            self.key = Some(key.to_string());
+        }
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
     }
 }
 
@@ -20,31 +49,61 @@ pub enum ErrorKind {
     MissingStringTerminator,
     StringLengthError(usize, usize),
     UnknownTypeId(u32),
+    InvalidPayload(u32),
     NumericOverflow,
     TypeMismatch,
     TrailingCharacters,
+    UnexpectedEnd,
     LengthNotGiven,
     InvalidKeyType,
+    NonNumericKey(String),
     TextEncodingError,
     InvalidHeader,
     UnsupportedValue,
+    /// The input goes past one of the deserializer's `Limits`, which was set to the given maximum.
+    LimitExceeded(Limit, usize),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+/// Which of the deserializer's `Limits` was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Entries,
+    KeyLength,
+    StringLength,
+    Depth,
+    TotalBytes,
 }
 
 impl ErrorKind {
     pub fn with<T>(self, offset: usize) -> Result<T, ErrorWithOffset> {
-        Err(ErrorWithOffset { offset: Some(offset), kind: self })
+        Err(ErrorWithOffset::new(offset, self))
+    }
+}
+
+impl From<ErrorKind> for ErrorWithOffset {
+    fn from(kind: ErrorKind) -> Self {
+        ErrorWithOffset { offset: None, key: None, kind }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ErrorKind {
+    fn from(e: std::io::Error) -> Self {
+        ErrorKind::Io(e)
     }
 }
 
 impl ser::Error for ErrorWithOffset {
     fn custom<T: Display>(msg: T) -> Self {
-        ErrorWithOffset { kind: ErrorKind::Message(msg.to_string()), offset: None }
+        ErrorKind::Message(msg.to_string()).into()
     }
 }
 
 impl de::Error for ErrorWithOffset {
     fn custom<T: Display>(msg: T) -> Self {
-        ErrorWithOffset { kind: ErrorKind::Message(msg.to_string()), offset: None }
+        ErrorKind::Message(msg.to_string()).into()
     }
 }
 
@@ -62,8 +121,11 @@ impl de::Error for ErrorKind {
 
 impl Display for ErrorWithOffset {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(offset) = self.offset {
-            write!(f, "At offset {offset}: ")?;
+        match (self.offset, &self.key) {
+            (Some(offset), Some(key)) => write!(f, "At offset {offset}, key \"{key}\": ")?,
+            (Some(offset), None) => write!(f, "At offset {offset}: ")?,
+            (None, Some(key)) => write!(f, "Key \"{key}\": ")?,
+            (None, None) => {},
         }
         write!(f, "{}", self.kind)
     }
@@ -72,14 +134,28 @@ impl Display for ErrorWithOffset {
 impl Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            ErrorKind::Message(msg) => f.write_str(&msg),
+            ErrorKind::Message(msg) => f.write_str(msg),
             ErrorKind::StringLengthError(s_len, doc_len) => write!(f, "String length {s_len} too long, only {doc_len} bytes left in document"),
+            ErrorKind::UnknownTypeId(ty) => write!(f, "Unknown value type {ty}"),
+            ErrorKind::InvalidPayload(ty) => write!(f, "Could not determine the length of a value of type {ty}"),
+            ErrorKind::NonNumericKey(key) => write!(f, "Key \"{key}\" is not an integer"),
+            ErrorKind::UnexpectedEnd => write!(f, "Unexpected end of input"),
             ErrorKind::InvalidHeader => write!(f, "The file header is invalid"),
             ErrorKind::UnsupportedValue => write!(f, "Unsupported value in input"),
+            ErrorKind::LimitExceeded(limit, max) => match limit {
+                Limit::Entries => write!(f, "More than {max} entries"),
+                Limit::KeyLength => write!(f, "Key longer than {max} bytes"),
+                Limit::StringLength => write!(f, "String longer than {max} bytes"),
+                Limit::Depth => write!(f, "Tables nested more than {max} deep"),
+                Limit::TotalBytes => write!(f, "Input longer than the budget of {max} bytes"),
+            },
+            #[cfg(feature = "std")]
+            ErrorKind::Io(e) => write!(f, "{e}"),
             _ => write!(f, "{:?}", self),
         }
     }
 }
 
-impl std::error::Error for ErrorKind {}
-impl std::error::Error for ErrorWithOffset {}
\ No newline at end of file
+// `StdError` is `std::error::Error` with the `std` feature, and serde's own stand-in without it
+impl ser::StdError for ErrorKind {}
+impl ser::StdError for ErrorWithOffset {}
\ No newline at end of file
@@ -0,0 +1,308 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use encoding_rs::WINDOWS_1252;
+
+use crate::registry::{PayloadLength, TypeRegistry};
+use crate::ser::to_bytes;
+use crate::value::{HashTable, RawValue, Value};
+
+/// Which characters generated keys and strings are made of. NUL is never used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Ascii,
+    /// Every character Windows-1252 can encode, as used by Construct Classic.
+    Windows1252,
+}
+
+impl Charset {
+    fn chars(self) -> Vec<char> {
+        let last = match self {
+            Charset::Ascii => 0x7F,
+            Charset::Windows1252 => 0xFF,
+        };
+        (1..=last).map(|byte: u8| WINDOWS_1252.decode_without_bom_handling(&[byte]).0.chars().next().unwrap()).collect()
+    }
+}
+
+/// Relative chances of each kind of value being generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueWeights {
+    pub int: u32,
+    pub float: u32,
+    pub string: u32,
+    /// Raw values have a `u32` byte count at the start of their payload,
+    /// so `GeneratorOptions::type_registry` can read them back.
+    pub raw: u32,
+}
+
+impl Default for ValueWeights {
+    fn default() -> Self {
+        ValueWeights { int: 1, float: 1, string: 1, raw: 0 }
+    }
+}
+
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+impl ValueWeights {
+    fn total(self) -> u64 {
+        [self.int, self.float, self.string, self.raw].into_iter().map(u64::from).sum()
+    }
+
+    /// The weights scaled down so that they add up to at most `u32::MAX`, as proptest's `Union` needs,
+    /// with nonzero weights staying nonzero.
+    #[cfg(feature = "proptest")]
+    fn fitted(self) -> Self {
+        // leave room for the weights that are rounded up to 1
+        let scale = self.total().div_ceil(u64::from(u32::MAX) - 4).max(1);
+        let fit = |weight: u32| if weight == 0 { 0 } else { (u64::from(weight) / scale).max(1) as u32 };
+        ValueWeights { int: fit(self.int), float: fit(self.float), string: fit(self.string), raw: fit(self.raw) }
+    }
+}
+
+/// How the `arbitrary` and `proptest` generators build tables.
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    /// Tables get up to this many entries.
+    pub max_entries: usize,
+    /// Keys are picked from these when there are any, and generated like string values otherwise.
+    pub keys: Vec<String>,
+    /// Whether a table may have several entries with the same key.
+    pub duplicate_keys: bool,
+    /// Generated keys and strings are up to this many characters long.
+    pub max_string_len: usize,
+    pub charset: Charset,
+    pub weights: ValueWeights,
+    /// The chance, out of 100, that a value is one of a fixed set of edge cases,
+    /// such as `i64::MIN`, NaN, `-0.0` or the empty string.
+    pub edge_case_percent: u8,
+    /// The type tag of raw values.
+    pub raw_type_id: u32,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            max_entries: 16,
+            keys: Vec::new(),
+            duplicate_keys: false,
+            max_string_len: 24,
+            charset: Charset::Windows1252,
+            weights: ValueWeights::default(),
+            edge_case_percent: 20,
+            raw_type_id: 3,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    /// A registry that accepts the raw values these options generate.
+    pub fn type_registry(&self) -> TypeRegistry {
+        let mut registry = TypeRegistry::new();
+        registry.register(self.raw_type_id, PayloadLength::Prefixed);
+        registry
+    }
+
+    /// Adds an entry unless its key is taken and duplicates aren't allowed.
+    fn push_entry(&self, table: &mut HashTable, key: String, value: Value) {
+        if self.duplicate_keys || !table.contains_key(&key) {
+            table.push(key, value);
+        }
+    }
+}
+
+const INT_EDGE_CASES: &[i64] = &[0, 1, -1, i64::MIN, i64::MAX, i32::MIN as i64, i32::MAX as i64, u32::MAX as i64];
+
+const FLOAT_EDGE_CASES: &[f64] = &[
+    0.0,
+    -0.0,
+    f64::NAN,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::MIN,
+    f64::MAX,
+    f64::MIN_POSITIVE,
+    // the smallest subnormal
+    5e-324,
+    f64::EPSILON,
+];
+
+/// Strings that Construct reads as numbers or are otherwise easy to mishandle.
+const STRING_EDGE_CASES: &[&str] = &["", " ", "0", "-1", "1.5", "1e3", "NaN", "true"];
+
+/// A generated table, along with its `MAP1.0` encoding.
+///
+/// Tables with NaN values don't compare equal to themselves, so compare `bytes` to check round trips.
+#[derive(Debug, Clone)]
+pub struct GeneratedTable {
+    pub table: HashTable,
+    pub bytes: Vec<u8>,
+}
+
+impl From<HashTable> for GeneratedTable {
+    fn from(table: HashTable) -> Self {
+        let bytes = to_bytes(&table).expect("generated tables can be encoded");
+        GeneratedTable { table, bytes }
+    }
+}
+
+/// A length-prefixed payload, as `PayloadLength::Prefixed` reads it.
+fn raw_value(type_id: u32, data: &[u8]) -> Value {
+    let mut payload = Vec::with_capacity(data.len() + 4);
+    payload.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    payload.extend_from_slice(data);
+    Value::Raw(RawValue { type_id, payload })
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls {
+    use super::*;
+    use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+    impl GeneratorOptions {
+        pub fn arbitrary_value(&self, u: &mut Unstructured) -> Result<Value> {
+            let ValueWeights { int, float, string, .. } = self.weights;
+            let total = self.weights.total();
+            if total == 0 {
+                return Err(Error::IncorrectFormat);
+            }
+            // `ratio` doesn't take a numerator of 0
+            let edge_case = self.edge_case_percent > 0 && u.ratio(self.edge_case_percent.min(100), 100)?;
+            let mut pick = u.int_in_range(0..=total - 1)?;
+            for (weight, kind) in [(int, 0), (float, 1), (string, 2)] {
+                let weight = u64::from(weight);
+                if pick >= weight {
+                    pick -= weight;
+                    continue;
+                }
+                return Ok(match (kind, edge_case) {
+                    (0, true) => Value::Int(*u.choose(INT_EDGE_CASES)?),
+                    (0, false) => Value::Int(u.arbitrary()?),
+                    (1, true) => Value::Float(*u.choose(FLOAT_EDGE_CASES)?),
+                    (1, false) => Value::Float(u.arbitrary()?),
+                    (_, true) => Value::String((*u.choose(STRING_EDGE_CASES)?).into()),
+                    (_, false) => Value::String(self.arbitrary_string(u)?),
+                });
+            }
+            let data: &[u8] = u.arbitrary()?;
+            Ok(raw_value(self.raw_type_id, data))
+        }
+
+        pub fn arbitrary_table(&self, u: &mut Unstructured) -> Result<HashTable> {
+            let len = u.int_in_range(0..=self.max_entries)?;
+            let mut table = HashTable::new();
+            for _ in 0..len {
+                let key = if self.keys.is_empty() { self.arbitrary_string(u)? } else { u.choose(&self.keys)?.clone() };
+                let value = self.arbitrary_value(u)?;
+                self.push_entry(&mut table, key, value);
+            }
+            Ok(table)
+        }
+
+        fn arbitrary_string(&self, u: &mut Unstructured) -> Result<String> {
+            let chars = self.charset.chars();
+            let len = u.int_in_range(0..=self.max_string_len)?;
+            (0..len).map(|_| u.choose(&chars).copied()).collect()
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Value {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            GeneratorOptions::default().arbitrary_value(u)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for HashTable {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            GeneratorOptions::default().arbitrary_table(u)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for GeneratedTable {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+            HashTable::arbitrary(u).map(GeneratedTable::from)
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+mod proptest_impls {
+    use super::*;
+    use proptest::prelude::*;
+    use proptest::sample::select;
+    use proptest::strategy::Union;
+
+    /// Picks from `edge_cases` `percent` times out of 100, and from `other` otherwise.
+    fn with_edge_cases<T: core::fmt::Debug + 'static>(percent: u8, edge_cases: BoxedStrategy<T>, other: BoxedStrategy<T>) -> BoxedStrategy<T> {
+        let percent = u32::from(percent.min(100));
+        let choices = [(percent, edge_cases), (100 - percent, other)];
+        Union::new_weighted(choices.into_iter().filter(|(weight, _)| *weight > 0).collect()).boxed()
+    }
+
+    impl GeneratorOptions {
+        pub fn value_strategy(&self) -> BoxedStrategy<Value> {
+            let edge = self.edge_case_percent;
+            let raw_type_id = self.raw_type_id;
+            let int = with_edge_cases(edge, select(INT_EDGE_CASES).boxed(), any::<i64>().boxed());
+            let float = with_edge_cases(edge, select(FLOAT_EDGE_CASES).boxed(), any::<f64>().boxed());
+            let string = with_edge_cases(edge, select(STRING_EDGE_CASES).prop_map(String::from).boxed(), self.string_strategy());
+            let raw = proptest::collection::vec(any::<u8>(), 0..=self.max_string_len).prop_map(move |data| raw_value(raw_type_id, &data));
+            let weights = self.weights.fitted();
+            let choices = [
+                (weights.int, int.prop_map(Value::Int).boxed()),
+                (weights.float, float.prop_map(Value::Float).boxed()),
+                (weights.string, string.prop_map(Value::String).boxed()),
+                (weights.raw, raw.boxed()),
+            ];
+            let choices: Vec<_> = choices.into_iter().filter(|(weight, _)| *weight > 0).collect();
+            assert!(!choices.is_empty(), "at least one value weight must be nonzero");
+            Union::new_weighted(choices).boxed()
+        }
+
+        pub fn table_strategy(&self) -> BoxedStrategy<HashTable> {
+            let key = if self.keys.is_empty() { self.string_strategy() } else { select(self.keys.clone()).boxed() };
+            let options = self.clone();
+            proptest::collection::vec((key, self.value_strategy()), 0..=self.max_entries)
+                .prop_map(move |entries| {
+                    let mut table = HashTable::new();
+                    for (key, value) in entries {
+                        options.push_entry(&mut table, key, value);
+                    }
+                    table
+                })
+                .boxed()
+        }
+
+        fn string_strategy(&self) -> BoxedStrategy<String> {
+            proptest::collection::vec(select(self.charset.chars()), 0..=self.max_string_len)
+                .prop_map(String::from_iter)
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Value {
+        type Parameters = GeneratorOptions;
+        type Strategy = BoxedStrategy<Value>;
+
+        fn arbitrary_with(options: GeneratorOptions) -> Self::Strategy {
+            options.value_strategy()
+        }
+    }
+
+    impl Arbitrary for HashTable {
+        type Parameters = GeneratorOptions;
+        type Strategy = BoxedStrategy<HashTable>;
+
+        fn arbitrary_with(options: GeneratorOptions) -> Self::Strategy {
+            options.table_strategy()
+        }
+    }
+
+    impl Arbitrary for GeneratedTable {
+        type Parameters = GeneratorOptions;
+        type Strategy = BoxedStrategy<GeneratedTable>;
+
+        fn arbitrary_with(options: GeneratorOptions) -> Self::Strategy {
+            options.table_strategy().prop_map(GeneratedTable::from).boxed()
+        }
+    }
+}
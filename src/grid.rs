@@ -0,0 +1,277 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+use core::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+/// How a grid is packed into a string value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridOptions {
+    pub row_separator: &'static str,
+    pub cell_separator: &'static str,
+    /// Whether every row, including the last, ends with the row separator.
+    pub terminated: bool,
+}
+
+/// A cell or row that couldn't be read or written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridError {
+    pub row: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Row {}, column {}: {}", self.row, self.column, self.message)
+    }
+}
+
+impl GridOptions {
+    pub const fn new(row_separator: &'static str, cell_separator: &'static str) -> Self {
+        GridOptions { row_separator, cell_separator, terminated: false }
+    }
+
+    /// Splits `s` into rows of cells, checking that every row has as many cells as the first.
+    /// An empty string has no rows.
+    pub fn split<'s>(&self, s: &'s str) -> Result<Vec<Vec<&'s str>>, GridError> {
+        let s = if self.terminated {
+            match s.strip_suffix(self.row_separator) {
+                Some(s) => s,
+                None if s.is_empty() => s,
+                None => {
+                    let row = s.matches(self.row_separator).count();
+                    return Err(GridError { row, column: 0, message: "the last row isn't terminated".to_string() });
+                },
+            }
+        } else {
+            s
+        };
+        if s.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rows: Vec<Vec<&str>> = s.split(self.row_separator).map(|row| row.split(self.cell_separator).collect()).collect();
+        check_width(rows.iter().map(Vec::len))?;
+        Ok(rows)
+    }
+
+    /// Splits `s` and parses each cell.
+    pub fn parse<T>(&self, s: &str) -> Result<Vec<Vec<T>>, GridError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let rows = self.split(s)?;
+        rows.iter().enumerate()
+            .map(|(row, cells)| {
+                cells.iter().enumerate()
+                    .map(|(column, cell)| {
+                        cell.parse().map_err(|e| {
+                            GridError { row, column, message: alloc::format!("{cell:?} could not be parsed: {e}") }
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Writes rows of cells as text, checking that every row has as many cells as the first.
+    pub fn join<T: Display>(&self, rows: &[Vec<T>]) -> Result<String, GridError> {
+        check_width(rows.iter().map(Vec::len))?;
+        let mut joined = String::new();
+        for (row, cells) in rows.iter().enumerate() {
+            if row > 0 {
+                joined.push_str(self.row_separator);
+            }
+            for (column, cell) in cells.iter().enumerate() {
+                if column > 0 {
+                    joined.push_str(self.cell_separator);
+                }
+                let cell = cell.to_string();
+                if cell.contains(self.row_separator) || cell.contains(self.cell_separator) {
+                    return Err(GridError { row, column, message: alloc::format!("{cell:?} contains a separator") });
+                }
+                joined.push_str(&cell);
+            }
+        }
+        if self.terminated && !rows.is_empty() {
+            joined.push_str(self.row_separator);
+        }
+        Ok(joined)
+    }
+}
+
+/// Checks that all rows are as wide as the first, which has at least one cell.
+/// A row without cells would be written as nothing, and read back as a row with one empty cell.
+fn check_width(widths: impl Iterator<Item = usize>) -> Result<(), GridError> {
+    let mut widths = widths.enumerate();
+    let Some((_, width)) = widths.next() else { return Ok(()) };
+    if width == 0 {
+        return Err(GridError { row: 0, column: 0, message: "rows need at least one cell".to_string() });
+    }
+    match widths.find(|&(_, len)| len != width) {
+        // the first cell that is missing, or the first one too many
+        Some((row, len)) => Err(GridError {
+            row,
+            column: len.min(width),
+            message: alloc::format!("expected {width} cells like the first row, found {len}"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The `GridOptions` a `Grid` uses.
+pub trait GridFormat {
+    const OPTIONS: GridOptions;
+}
+
+/// Rows on separate lines, with cells separated by commas.
+pub struct Lines;
+
+impl GridFormat for Lines {
+    const OPTIONS: GridOptions = GridOptions::new("\n", ",");
+}
+
+/// A width×height grid stored as one string value, with its cells in row-major order.
+pub struct Grid<T, F = Lines> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    format: PhantomData<F>,
+}
+
+impl<T, F> Grid<T, F> {
+    /// A grid with every cell set to `value`.
+    ///
+    /// Panics if `width` is 0 but `height` isn't, as rows without cells can't be written,
+    /// or if the number of cells overflows `usize`.
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        assert!(width > 0 || height == 0, "rows of a grid need at least one cell");
+        let len = width.checked_mul(height).expect("too many cells for a grid");
+        Grid { width, height, cells: alloc::vec![value; len], format: PhantomData }
+    }
+
+    /// A grid of `rows`, which must all have the same length and at least one cell.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        check_width(rows.iter().map(Vec::len))?;
+        let width = rows.first().map_or(0, Vec::len);
+        let height = rows.len();
+        let cells = rows.into_iter().flatten().collect();
+        Ok(Grid { width, height, cells, format: PhantomData })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get(y * self.width + x)
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get_mut(y * self.width + x)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|y| &self.cells[y * self.width..(y + 1) * self.width])
+    }
+
+    pub fn into_rows(self) -> Vec<Vec<T>> {
+        let width = self.width;
+        let height = self.height;
+        let mut cells = self.cells.into_iter();
+        (0..height).map(|_| cells.by_ref().take(width).collect()).collect()
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Grid<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.rows()).finish()
+    }
+}
+
+impl<T: Clone, F> Clone for Grid<T, F> {
+    fn clone(&self) -> Self {
+        Grid { width: self.width, height: self.height, cells: self.cells.clone(), format: PhantomData }
+    }
+}
+
+impl<T: PartialEq, F> PartialEq for Grid<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.cells == other.cells
+    }
+}
+
+impl<T: Display, F: GridFormat> Serialize for Grid<T, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Vec<&T>> = self.rows().map(|row| row.iter().collect()).collect();
+        serialize_grid(&rows, F::OPTIONS, serializer)
+    }
+}
+
+impl<'de, T, F> Deserialize<'de> for Grid<T, F>
+where
+    T: FromStr,
+    T::Err: Display,
+    F: GridFormat,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = deserialize_grid(F::OPTIONS, deserializer)?;
+        Grid::from_rows(rows).map_err(de::Error::custom)
+    }
+}
+
+pub(crate) fn serialize_grid<T, S>(rows: &[Vec<T>], options: GridOptions, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    let joined = options.join(rows).map_err(ser::Error::custom)?;
+    serializer.serialize_str(&joined)
+}
+
+pub(crate) fn deserialize_grid<'de, T, D>(options: GridOptions, deserializer: D) -> Result<Vec<Vec<T>>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(GridVisitor { options, cells: PhantomData })
+}
+
+struct GridVisitor<T> {
+    options: GridOptions,
+    cells: PhantomData<T>,
+}
+
+impl<T> Visitor<'_> for GridVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = Vec<Vec<T>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string of rows of cells")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<Vec<T>>, E> {
+        self.options.parse(v).map_err(E::custom)
+    }
+}
@@ -1,9 +1,50 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 mod ser;
 mod de;
 mod constants;
 mod error;
+mod registry;
+mod value;
+mod convert;
+mod spanned;
+mod document;
+mod view;
+mod tokenized;
+mod grid;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod generate;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "tokio")]
+mod async_io;
+pub mod with;
+pub mod testing;
+#[doc(hidden)]
+#[path = "private.rs"]
+pub mod __private;
 
 pub use ser::*;
 pub use de::*;
 pub use constants::*;
-pub use error::*;
\ No newline at end of file
+pub use error::*;
+pub use registry::*;
+pub use value::*;
+pub use convert::*;
+pub use spanned::*;
+pub use document::*;
+pub use view::*;
+pub use tokenized::*;
+pub use grid::*;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub use generate::*;
+#[cfg(feature = "mmap")]
+pub use mmap::*;
+#[cfg(feature = "tokio")]
+pub use async_io::*;
+#[cfg(feature = "derive")]
+pub use serde_construct_classic_derive::ConstructTable;
@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+use serde::de::DeserializeOwned;
+
+use crate::de::{from_bytes_with_options, DeserializerOptions};
+use crate::error::{ErrorKind, ErrorWithOffset};
+use crate::view::TableView;
+
+type Result<T> = std::result::Result<T, ErrorWithOffset>;
+
+/// Maps a file into memory.
+///
+/// The file must not be modified by another process while it is mapped,
+/// as the mapping would change underneath the deserializer.
+fn map_file(path: &Path) -> Result<Mmap> {
+    let file = File::open(path).map_err(ErrorKind::Io)?;
+    // SAFETY: the mapping is read-only, and the caveat about concurrent modification is documented
+    // on the public functions.
+    let map = unsafe { Mmap::map(&file) }.map_err(ErrorKind::Io)?;
+    Ok(map)
+}
+
+/// Deserializes a table file by memory-mapping it rather than reading it into memory.
+/// Error offsets are relative to the start of the file.
+///
+/// The file must not be modified while it's being read.
+pub fn from_path<T, P>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    from_path_with_options(path, DeserializerOptions::default())
+}
+
+/// Like `from_path`, with options.
+///
+/// The file must not be modified while it's being read.
+pub fn from_path_with_options<T, P>(path: P, options: DeserializerOptions) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let map = map_file(path.as_ref())?;
+    from_bytes_with_options(&map, options)
+}
+
+impl TableView<'static> {
+    /// Memory-maps a table file and indexes it.
+    ///
+    /// The file must not be modified for as long as the view exists.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_options(path, DeserializerOptions::default())
+    }
+
+    /// Like `open`, with options.
+    ///
+    /// The file must not be modified for as long as the view exists.
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: DeserializerOptions) -> Result<Self> {
+        TableView::from_map(map_file(path.as_ref())?, options)
+    }
+}
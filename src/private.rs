@@ -0,0 +1,192 @@
+//! Support code for `#[derive(ConstructTable)]` and `table!`. Not public API.
+
+use alloc::borrow::Cow;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+pub use alloc::string::ToString;
+pub use serde;
+
+use crate::value::Value;
+use crate::with::Number;
+
+/// Converts a literal value in `table!`. Implemented for a single integer type, so an unsuffixed
+/// integer literal is an `i64` instead of falling back to `i32`.
+pub trait Literal {
+    fn into_value(self) -> Value;
+}
+
+impl Literal for i64 {
+    fn into_value(self) -> Value {
+        Value::Int(self)
+    }
+}
+
+impl Literal for bool {
+    fn into_value(self) -> Value {
+        Value::Int(i64::from(self))
+    }
+}
+
+impl Literal for f32 {
+    fn into_value(self) -> Value {
+        Value::Float(f64::from(self))
+    }
+}
+
+impl Literal for f64 {
+    fn into_value(self) -> Value {
+        Value::Float(self)
+    }
+}
+
+impl Literal for &str {
+    fn into_value(self) -> Value {
+        Value::from(self)
+    }
+}
+
+/// Converts a literal after `float` in `table!`, with integer literals read as `i64` like `Literal`.
+pub trait FloatLiteral {
+    fn into_f64(self) -> f64;
+}
+
+impl FloatLiteral for i64 {
+    fn into_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl FloatLiteral for f32 {
+    fn into_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl FloatLiteral for f64 {
+    fn into_f64(self) -> f64 {
+        self
+    }
+}
+
+/// A field stored with the integer type tag.
+pub struct Int<T>(pub T);
+
+/// A field stored with the float type tag.
+pub struct Float<T>(pub T);
+
+impl<T: Number> Serialize for Int<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.to_i64() {
+            Some(v) => serializer.serialize_i64(v),
+            None => Err(ser::Error::custom("value cannot be stored as an integer")),
+        }
+    }
+}
+
+impl<T: Number> Serialize for Float<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0.to_f64())
+    }
+}
+
+impl<'de, T: Number> Deserialize<'de> for Int<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_i64(NumberVisitor(PhantomData)).map(Int)
+    }
+}
+
+impl<'de, T: Number> Deserialize<'de> for Float<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_f64(NumberVisitor(PhantomData)).map(Float)
+    }
+}
+
+/// Converts whichever number the deserializer produces; the Hash Table deserializer
+/// has already checked the type tag by then.
+struct NumberVisitor<T>(PhantomData<T>);
+
+impl<T: Number> Visitor<'_> for NumberVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(T::EXPECTING)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<T, E> {
+        self.visit_i64(i64::from(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        T::from_i64(v).ok_or_else(|| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        let i = i64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))?;
+        self.visit_i64(i)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+        T::from_f64(v).ok_or_else(|| E::invalid_value(de::Unexpected::Float(v), &self))
+    }
+}
+
+/// A table key, borrowed from the input when possible.
+pub struct Key<'de>(pub Cow<'de, str>);
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl<'de> Visitor<'de> for KeyVisitor {
+            type Value = Key<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a key")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Key<'de>, E> {
+                Ok(Key(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Key<'de>, E> {
+                Ok(Key(Cow::Owned(v.into())))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+/// Produces the value of a field that is missing from the table: `None` for options,
+/// and a `missing_field` error for everything else.
+pub fn missing_field<'de, T, E>(field: &'static str) -> Result<T, E>
+where
+    T: Deserialize<'de>,
+    E: de::Error,
+{
+    T::deserialize(MissingField(field, PhantomData))
+}
+
+struct MissingField<E>(&'static str, PhantomData<E>);
+
+impl<'de, E: de::Error> Deserializer<'de> for MissingField<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, E> {
+        Err(E::missing_field(self.0))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_none()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::collections::BTreeMap;
+
+use crate::constants::*;
+
+/// How far the payload of a value with a non-standard type tag extends.
+#[derive(Debug, Clone, Copy)]
+pub enum PayloadLength {
+    /// The payload is always this many bytes long.
+    Fixed(usize),
+    /// The payload begins with a little-endian `u32` byte count, which is kept as part of the payload.
+    Prefixed,
+    /// The payload runs up to the next offset that plausibly starts another key, or to the end of the table.
+    UntilNextKey,
+    /// The payload length is computed from the bytes following the type tag.
+    /// Returning `None` rejects the value as malformed.
+    Custom(fn(&[u8]) -> Option<usize>),
+}
+
+/// Type tags beyond `TYPE_I64`, `TYPE_F64` and `TYPE_STRING` that the deserializer should accept,
+/// keeping their payloads as raw bytes.
+#[derive(Debug, Clone, Default)]
+pub struct TypeRegistry {
+    handlers: BTreeMap<u32, PayloadLength>,
+    fallback: Option<PayloadLength>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry that accepts every unknown tag, measuring its payload with `fallback`.
+    pub fn with_fallback(fallback: PayloadLength) -> Self {
+        TypeRegistry { handlers: BTreeMap::new(), fallback: Some(fallback) }
+    }
+
+    /// Registers the payload length of `type_id`, returning the previously registered one.
+    pub fn register(&mut self, type_id: u32, length: PayloadLength) -> Option<PayloadLength> {
+        self.handlers.insert(type_id, length)
+    }
+
+    /// Sets how the payload of tags without a registered handler is measured.
+    /// With no fallback, those tags fail with `UnknownTypeId`.
+    pub fn set_fallback(&mut self, fallback: Option<PayloadLength>) {
+        self.fallback = fallback;
+    }
+
+    pub fn get(&self, type_id: u32) -> Option<PayloadLength> {
+        self.handlers.get(&type_id).copied().or(self.fallback)
+    }
+
+    fn is_known(&self, type_id: u32) -> bool {
+        matches!(type_id, TYPE_I64 | TYPE_F64 | TYPE_STRING) || self.handlers.contains_key(&type_id)
+    }
+
+    /// Measures the payload at the start of `input` (just past the type tag).
+    /// Returns `None` if the payload doesn't fit in `input`.
+    pub(crate) fn payload_len(&self, length: PayloadLength, input: &[u8]) -> Option<usize> {
+        let len = match length {
+            PayloadLength::Fixed(len) => len,
+            PayloadLength::Prefixed => {
+                let prefix = u32::from_le_bytes(input.get(..4)?.try_into().unwrap()) as usize;
+                prefix.checked_add(4)?
+            },
+            PayloadLength::UntilNextKey => {
+                (0..=input.len()).find(|&offset| self.is_plausible_entry(&input[offset..]))?
+            },
+            PayloadLength::Custom(f) => f(input)?,
+        };
+        (len <= input.len()).then_some(len)
+    }
+
+    /// Whether `input` is empty or starts with a NUL-terminated key followed by a value with a known type tag.
+    fn is_plausible_entry(&self, input: &[u8]) -> bool {
+        if input.is_empty() {
+            return true;
+        }
+        let Some(key_len) = read_u32(input) else { return false };
+        let key_len = key_len as usize;
+        let Some(key) = input.get(4..4 + key_len) else { return false };
+        if key.last() != Some(&0) || key[..key_len - 1].contains(&0) {
+            return false;
+        }
+        let value = &input[4 + key_len..];
+        match read_u32(value) {
+            Some(TYPE_I64 | TYPE_F64) => value.len() >= 12,
+            Some(TYPE_STRING) => match read_u32(&value[4..]) {
+                Some(len) => len > 0 && value.get(7 + len as usize) == Some(&0),
+                None => false,
+            },
+            Some(type_id) => self.is_known(type_id),
+            None => false,
+        }
+    }
+}
+
+fn read_u32(input: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(input.get(..4)?.try_into().unwrap()))
+}
@@ -1,133 +1,464 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::io::{self, Seek, SeekFrom, Write};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use encoding_rs::{self, WINDOWS_1252};
 use serde::{ser, Serialize};
 
 use crate::constants::*;
 use crate::error::ErrorKind as Error;
+use crate::value::{RAW_PAYLOAD_KEY, RAW_TYPE_KEY};
 
-type Result<T> = std::result::Result<T, Error>;
+type Result<T> = core::result::Result<T, Error>;
 
-pub struct Serializer {
-    output: Vec<u8>,
+pub struct Serializer<O = Vec<u8>> {
+    output: O,
     writing_value: bool,
     writing_key: bool,
+    /// Entries written to the table so far.
+    entry_count: u32,
+    /// Where the key count goes, if it wasn't known when the table was started.
+    key_count_position: Option<u64>,
+    options: SerializerOptions,
+    /// The entry being written, while entries are collected for sorting.
+    entry_buffer: Option<Vec<u8>>,
+    sorted_entries: Vec<SortedEntry>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SerializerOptions {
+    /// Write the entries of the table ordered by key instead of in the order they are serialized,
+    /// so that e.g. a `HashMap` always produces the same bytes.
+    pub sort_keys: Option<KeyOrder>,
+    /// Write a struct or map in place of a value as a table of its own, header included, with no type tag
+    /// before it. Construct itself can't read such tables, so without this they fail with `UnsupportedValue`.
+    pub nested_tables: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// By Unicode code point.
+    Unicode,
+    /// By the Windows-1252 bytes the keys are stored as.
+    Windows1252,
+    /// By Unicode code point, except that runs of digits compare by their numeric value,
+    /// so that `level2` sorts before `level10`.
+    Natural,
+}
+
+impl KeyOrder {
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            KeyOrder::Unicode => a.cmp(b),
+            KeyOrder::Windows1252 => WINDOWS_1252.encode(a).0.cmp(&WINDOWS_1252.encode(b).0),
+            KeyOrder::Natural => natural_cmp(a, b),
+        }
+    }
+}
+
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let (digits_a, rest_a) = a.split_at(a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len()));
+            let (digits_b, rest_b) = b.split_at(b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len()));
+            let (value_a, value_b) = (digits_a.trim_start_matches('0'), digits_b.trim_start_matches('0'));
+            // equal numbers with more leading zeros go last
+            let ordering = value_a.len().cmp(&value_b.len())
+                .then_with(|| value_a.cmp(value_b))
+                .then_with(|| digits_a.len().cmp(&digits_b.len()));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+/// A serialized key and value, held back until all entries can be written in order.
+struct SortedEntry {
+    key: String,
+    key_bytes_len: usize,
+    bytes: Vec<u8>,
+}
+
+impl SortedEntry {
+    /// The key as stored, without its length prefix and NUL terminator.
+    fn key_bytes(&self) -> &[u8] {
+        &self.bytes[4..self.key_bytes_len - 1]
+    }
+}
+
+/// Where a `Serializer` writes the table. Besides appending, it must be able to go back
+/// and fill in the key count of a table whose length wasn't known up front.
+pub trait Output {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()>;
+
+    /// The position the next byte will be written at.
+    fn position(&self) -> u64;
+
+    /// Overwrites previously written bytes starting at `position`.
+    fn patch(&mut self, position: u64, bytes: &[u8]) -> Result<()>;
+}
+
+impl Output for Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn patch(&mut self, position: u64, bytes: &[u8]) -> Result<()> {
+        let start = position as usize;
+        self[start..start + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Adapts a seekable writer into an `Output`.
+#[cfg(feature = "std")]
+pub struct SeekWriter<W> {
+    writer: W,
+    position: u64,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Seek> SeekWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let position = writer.stream_position()?;
+        Ok(SeekWriter { writer, position })
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Seek> Output for SeekWriter<W> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn patch(&mut self, position: u64, bytes: &[u8]) -> Result<()> {
+        self.writer.seek(SeekFrom::Start(position))?;
+        self.writer.write_all(bytes)?;
+        self.writer.seek(SeekFrom::Start(self.position))?;
+        Ok(())
+    }
 }
 
 pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
-    T: Serialize,
+    T: ?Sized + Serialize,
+{
+    to_bytes_with_options(value, SerializerOptions::default())
+}
+
+pub fn to_bytes_with_options<T>(value: &T, options: SerializerOptions) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
 {
-    let mut serializer = Serializer {
-        output: Vec::new(),
-        writing_key: false,
-        writing_value: false,
-    };
+    let mut serializer = Serializer::with_options(Vec::new(), options);
     value.serialize(&mut serializer)?;
     Ok(serializer.output)
 }
 
+#[cfg(feature = "std")]
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: Write + Seek,
+    T: ?Sized + Serialize,
+{
+    to_writer_with_options(writer, value, SerializerOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub fn to_writer_with_options<W, T>(writer: W, value: &T, options: SerializerOptions) -> Result<()>
+where
+    W: Write + Seek,
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::with_options(SeekWriter::new(writer)?, options);
+    value.serialize(&mut serializer)?;
+    serializer.output.writer.flush()?;
+    Ok(())
+}
+
+impl<O: Output> Serializer<O> {
+    pub fn new(output: O) -> Self {
+        Self::with_options(output, SerializerOptions::default())
+    }
+
+    pub fn with_options(output: O, options: SerializerOptions) -> Self {
+        Serializer {
+            output,
+            writing_key: false,
+            writing_value: false,
+            entry_count: 0,
+            key_count_position: None,
+            options,
+            entry_buffer: None,
+            sorted_entries: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> O {
+        self.output
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        match &mut self.entry_buffer {
+            Some(buffer) => {
+                buffer.extend_from_slice(bytes);
+                Ok(())
+            },
+            None => self.output.write_bytes(bytes),
+        }
+    }
+
+    fn write_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.entry_count += 1;
+        if self.options.sort_keys.is_some() {
+            self.entry_buffer = Some(Vec::new());
+        }
+        self.writing_key = true;
+        let result = key.serialize(&mut *self);
+        self.writing_key = false;
+        result
+    }
+
+    fn write_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.write_value_with(|ser| value.serialize(ser))
+    }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+    fn write_value_with(&mut self, write: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let key_bytes_len = self.entry_buffer.as_ref().map_or(0, Vec::len);
+        self.writing_value = true;
+        let result = write(self);
+        self.writing_value = false;
+        if let Some(bytes) = self.entry_buffer.take() {
+            let (key, _) = WINDOWS_1252.decode_without_bom_handling(&bytes[4..key_bytes_len - 1]);
+            let key = key.into_owned();
+            self.sorted_entries.push(SortedEntry { key, key_bytes_len, bytes });
+        }
+        result
+    }
+
+    fn end_table(&mut self) -> Result<()> {
+        if let Some(order) = self.options.sort_keys {
+            let mut entries = core::mem::take(&mut self.sorted_entries);
+            match order {
+                KeyOrder::Windows1252 => entries.sort_by(|a, b| a.key_bytes().cmp(b.key_bytes())),
+                order => entries.sort_by(|a, b| order.compare(&a.key, &b.key)),
+            }
+            for entry in entries {
+                self.output.write_bytes(&entry.bytes)?;
+            }
+        }
+        if let Some(position) = self.key_count_position.take() {
+            self.output.patch(position, &self.entry_count.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes a value the way it appears in a table: its type tag followed by its payload.
+pub(crate) fn encode_value<T>(value: &T, options: SerializerOptions) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::with_options(Vec::new(), options);
+    serializer.writing_value = true;
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+/// Encodes a key the way it appears in a table: length prefix, text and NUL terminator.
+pub(crate) fn encode_key<T>(key: &T) -> Result<Vec<u8>>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::new(Vec::new());
+    serializer.writing_key = true;
+    key.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+/// The text a key is stored as, such as the decimal text of a number.
+pub(crate) fn key_text<T>(key: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let encoded = encode_key(key)?;
+    // skip the length prefix and the terminating NUL byte
+    let (key, _) = WINDOWS_1252.decode_without_bom_handling(&encoded[4..encoded.len() - 1]);
+    Ok(key.into_owned())
+}
+
+/// The entries of a map in place of a value whose first key is `$type` or `$raw`, collected until the map ends.
+/// Like `Value` reads them, a map of just a `$type` integer and a base64 `$raw` string is written as a value
+/// with that type tag, and any other map as a nested table.
+#[derive(Default)]
+struct RawCapture {
+    key: Option<String>,
+    /// The keys with their encoded values.
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl RawCapture {
+    /// The type tag and payload the entries describe, if they are those of a raw value.
+    fn raw_value(&self) -> Option<(u32, Vec<u8>)> {
+        let [(first, _), (second, _)] = &self.entries[..] else { return None };
+        if first == second {
+            return None;
+        }
+        let (mut type_id, mut payload) = (None, None);
+        for (key, encoded) in &self.entries {
+            let (tag, bytes) = encoded.split_at_checked(4)?;
+            match (key.as_str(), u32::from_le_bytes(tag.try_into().unwrap())) {
+                (RAW_TYPE_KEY, TYPE_I64) => type_id = u32::try_from(i64::from_le_bytes(bytes.try_into().ok()?)).ok(),
+                (RAW_PAYLOAD_KEY, TYPE_STRING) => {
+                    // skip the length prefix and the terminating NUL byte
+                    let (text, _) = WINDOWS_1252.decode_without_bom_handling(&bytes[4..bytes.len() - 1]);
+                    payload = BASE64.decode(text.as_bytes()).ok();
+                },
+                _ => return None,
+            }
+        }
+        Some((type_id?, payload?))
+    }
+}
+
+impl<'a, O: Output> ser::Serializer for &'a mut Serializer<O> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
     type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
+    type SerializeMap = Compound<'a, O>;
+    type SerializeStruct = Compound<'a, O>;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, v: bool) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_bool(self, v: bool) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_i8(self, v: i8) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_i8(self, v: i8) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_i16(self, v: i16) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_i16(self, v: i16) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_i32(self, v: i32) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_i32(self, v: i32) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_i64(self, v: i64) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
-        if self.writing_key { return Err(Error::InvalidKeyType); }
+    fn serialize_i64(self, v: i64) -> core::result::Result<Self::Ok, Self::Error> {
+        // keys are always strings, so numeric keys (and bools) are stored as their decimal text
+        if self.writing_key { return self.serialize_str(&v.to_string()); }
         if self.writing_value {
-            self.output.extend(TYPE_I64.to_le_bytes());
+            self.write(&TYPE_I64.to_le_bytes())?;
         }
-        self.output.extend(v.to_le_bytes());
+        self.write(&v.to_le_bytes())?;
         Ok(())
     }
 
-    fn serialize_u8(self, v: u8) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_u8(self, v: u8) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_u16(self, v: u16) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_u16(self, v: u16) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_u32(self, v: u32) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_u32(self, v: u32) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::from(v))
     }
 
-    fn serialize_u64(self, v: u64) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_u64(self, v: u64) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_i64(i64::try_from(v).or(Err(Error::NumericOverflow))?)
     }
 
-    fn serialize_f32(self, v: f32) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_f32(self, v: f32) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_f64(f64::from(v))
     }
 
-    fn serialize_f64(self, v: f64) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_f64(self, v: f64) -> core::result::Result<Self::Ok, Self::Error> {
         if self.writing_key { return Err(Error::InvalidKeyType); }
         if self.writing_value {
-            self.output.extend(TYPE_F64.to_le_bytes());
+            self.write(&TYPE_F64.to_le_bytes())?;
         }
-        self.output.extend(v.to_le_bytes());
+        self.write(&v.to_le_bytes())?;
         Ok(())
     }
 
-    fn serialize_char(self, v: char) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_char(self, v: char) -> core::result::Result<Self::Ok, Self::Error> {
         self.serialize_str(&v.to_string())
     }
 
-    fn serialize_str(self, v: &str) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_str(self, v: &str) -> core::result::Result<Self::Ok, Self::Error> {
         if self.writing_value {
-            self.output.extend(TYPE_STRING.to_le_bytes());
+            self.write(&TYPE_STRING.to_le_bytes())?;
         }
         let (bytes, _, encoding_errors) = WINDOWS_1252.encode(v);
         let len = u32::try_from(bytes.len() + 1).or(Err(Error::NumericOverflow))?;
-        self.output.extend(len.to_le_bytes());
+        self.write(&len.to_le_bytes())?;
         if encoding_errors { return Err(Error::TextEncodingError); }
-        self.output.extend(bytes.to_vec());
-        self.output.push(0);
+        self.write(&bytes)?;
+        self.write(&[0])?;
         Ok(())
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_bytes(self, _v: &[u8]) -> core::result::Result<Self::Ok, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_none(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_none(self) -> core::result::Result<Self::Ok, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_some<T: ?Sized>(self, value: &T) -> std::prelude::v1::Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> core::result::Result<Self::Ok, Self::Error>
     where
-        T: Serialize
+        T: ?Sized + Serialize
     {
         value.serialize(self)
     }
 
-    fn serialize_unit(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_unit(self) -> core::result::Result<Self::Ok, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_unit_struct(self, _name: &'static str) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn serialize_unit_struct(self, _name: &'static str) -> core::result::Result<Self::Ok, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
@@ -136,37 +467,37 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _name: &'static str,
         _variant_index: u32,
         _variant: &'static str,
-    ) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    ) -> core::result::Result<Self::Ok, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_newtype_struct<T: ?Sized>(
+    fn serialize_newtype_struct<T>(
         self,
         _name: &'static str,
         value: &T,
-    ) -> std::prelude::v1::Result<Self::Ok, Self::Error>
+    ) -> core::result::Result<Self::Ok, Self::Error>
     where
-        T: Serialize
+        T: ?Sized + Serialize
     {
         value.serialize(self)
     }
 
-    fn serialize_newtype_variant<T: ?Sized>(
+    fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
         _variant_index: u32,
         _variant: &'static str,
         _value: &T,
-    ) -> std::prelude::v1::Result<Self::Ok, Self::Error>
-    where T: Serialize {
+    ) -> core::result::Result<Self::Ok, Self::Error>
+    where T: ?Sized + Serialize {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_seq(self, _len: Option<usize>) -> std::prelude::v1::Result<Self::SerializeSeq, Self::Error> {
+    fn serialize_seq(self, _len: Option<usize>) -> core::result::Result<Self::SerializeSeq, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_tuple(self, _len: usize) -> std::prelude::v1::Result<Self::SerializeTuple, Self::Error> {
+    fn serialize_tuple(self, _len: usize) -> core::result::Result<Self::SerializeTuple, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
@@ -174,7 +505,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self,
         _name: &'static str,
         _len: usize,
-    ) -> std::prelude::v1::Result<Self::SerializeTupleStruct, Self::Error> {
+    ) -> core::result::Result<Self::SerializeTupleStruct, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
@@ -184,34 +515,40 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _variant_index: u32,
         _variant: &'static str,
         _len: usize,
-    ) -> std::prelude::v1::Result<Self::SerializeTupleVariant, Self::Error> {
+    ) -> core::result::Result<Self::SerializeTupleVariant, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 
-    fn serialize_map(self, len: Option<usize>) -> std::prelude::v1::Result<Self::SerializeMap, Self::Error> {
+    fn serialize_map(self, len: Option<usize>) -> core::result::Result<Self::SerializeMap, Self::Error> {
         if self.writing_key { return Err(Error::InvalidKeyType); }
-        if self.writing_value { return Err(Error::UnsupportedValue); }
-        let Some(len) = len else {
-            return Err(Error::LengthNotGiven);
+        if self.writing_value {
+            // a value with a non-standard type tag or a nested table, which the first key tells apart
+            return Ok(Compound { ser: self, value: ValueMap::Undecided(len) });
+        }
+        self.output.write_bytes(MAGIC)?;
+        self.entry_count = 0;
+        let len = match len {
+            Some(len) => u32::try_from(len).or(Err(Error::NumericOverflow))?,
+            None => {
+                // reserve the key count, to be filled in once the entries have been counted
+                self.key_count_position = Some(self.output.position());
+                0
+            },
         };
-        self.output.extend([
-            0x4D,
-            0x41,
-            0x50,
-            0x31,
-            0x2E,
-            0x30,
-        ]);
-        let len = u32::try_from(len).or(Err(Error::NumericOverflow))?;
-        self.output.extend(len.to_le_bytes());
-        Ok(self)
+        self.output.write_bytes(&len.to_le_bytes())?;
+        Ok(Compound { ser: self, value: ValueMap::Table })
     }
 
     fn serialize_struct(
         self,
         _name: &'static str,
         len: usize,
-    ) -> std::prelude::v1::Result<Self::SerializeStruct, Self::Error> {
+    ) -> core::result::Result<Self::SerializeStruct, Self::Error> {
+        if self.writing_key { return Err(Error::InvalidKeyType); }
+        if self.writing_value {
+            let value = ValueMap::nested(Some(len), &self.options)?;
+            return Ok(Compound { ser: self, value });
+        }
         self.serialize_map(Some(len))
     }
 
@@ -221,13 +558,116 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         _variant_index: u32,
         _variant: &'static str,
         _len: usize,
-    ) -> std::prelude::v1::Result<Self::SerializeStructVariant, Self::Error> {
+    ) -> core::result::Result<Self::SerializeStructVariant, Self::Error> {
         Err(Error::UnsupportedValue)
     }
 }
 
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+/// Writes the entries of a map or struct: those of the table itself, or of a map or struct
+/// in place of a value.
+pub struct Compound<'a, O> {
+    ser: &'a mut Serializer<O>,
+    value: ValueMap,
+}
+
+/// What a map or struct in place of a value turned out to be.
+enum ValueMap {
+    /// Not a value: the entries are the table's own.
+    Table,
+    /// A map whose first key hasn't been seen yet, holding its length.
+    Undecided(Option<usize>),
+    /// A map starting with a `$type` or `$raw` key, which may be a raw value.
+    Raw(RawCapture),
+    /// Any other map or struct, written as a table of its own where the value goes.
+    Nested(Serializer<Vec<u8>>),
+}
+
+impl ValueMap {
+    fn nested(len: Option<usize>, options: &SerializerOptions) -> Result<Self> {
+        nested_table(len, options).map(ValueMap::Nested)
+    }
+}
+
+/// Starts a table to be written in place of a value.
+fn nested_table(len: Option<usize>, options: &SerializerOptions) -> Result<Serializer<Vec<u8>>> {
+    if !options.nested_tables {
+        return Err(Error::UnsupportedValue);
+    }
+    let mut table = Serializer::with_options(Vec::new(), options.clone());
+    ser::Serializer::serialize_map(&mut table, len)?;
+    Ok(table)
+}
+
+impl<O: Output> Compound<'_, O> {
+    fn serialize_entry_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let ValueMap::Undecided(len) = self.value {
+            // keys can only be serialized once, so keep the text to write after deciding
+            let key = key_text(key)?;
+            self.value = match key.as_str() {
+                RAW_TYPE_KEY | RAW_PAYLOAD_KEY => ValueMap::Raw(RawCapture::default()),
+                _ => ValueMap::nested(len, &self.ser.options)?,
+            };
+            return self.serialize_entry_key(&key);
+        }
+        match &mut self.value {
+            ValueMap::Table | ValueMap::Undecided(_) => self.ser.write_key(key),
+            ValueMap::Raw(raw) => {
+                raw.key = Some(key_text(key)?);
+                Ok(())
+            },
+            ValueMap::Nested(table) => table.write_key(key),
+        }
+    }
+
+    fn serialize_entry_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match &mut self.value {
+            ValueMap::Table | ValueMap::Undecided(_) => self.ser.write_value(value),
+            ValueMap::Raw(raw) => {
+                let key = raw.key.take().ok_or(Error::UnsupportedValue)?;
+                raw.entries.push((key, encode_value(value, self.ser.options.clone())?));
+                Ok(())
+            },
+            ValueMap::Nested(table) => table.write_value(value),
+        }
+    }
+
+    fn end_entries(self) -> Result<()> {
+        let value = match self.value {
+            // a map with no entries is an empty table
+            ValueMap::Undecided(len) => ValueMap::nested(len, &self.ser.options)?,
+            value => value,
+        };
+        match value {
+            ValueMap::Table | ValueMap::Undecided(_) => self.ser.end_table(),
+            ValueMap::Raw(raw) => {
+                if let Some((type_id, payload)) = raw.raw_value() {
+                    self.ser.write(&type_id.to_le_bytes())?;
+                    return self.ser.write(&payload);
+                }
+                let mut table = nested_table(Some(raw.entries.len()), &self.ser.options)?;
+                for (key, encoded) in &raw.entries {
+                    table.write_key(key)?;
+                    table.write_value_with(|table| table.write(encoded))?;
+                }
+                table.end_table()?;
+                self.ser.write(&table.output)
+            },
+            ValueMap::Nested(mut table) => {
+                table.end_table()?;
+                self.ser.write(&table.output)
+            },
+        }
+    }
+}
+
+impl<O: Output> ser::SerializeMap for Compound<'_, O> {
     type Ok = ();
     type Error = Error;
 
@@ -235,10 +675,7 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.writing_key = true;
-        let result = key.serialize(&mut **self);
-        self.writing_key = false;
-        result
+        self.serialize_entry_key(key)
     }
 
     // It doesn't make a difference whether the colon is printed at the end of
@@ -248,20 +685,17 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.writing_value = true;
-        let result = value.serialize(&mut **self);
-        self.writing_value = false;
-        result
+        self.serialize_entry_value(value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_entries()
     }
 }
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<O: Output> ser::SerializeStruct for Compound<'_, O> {
     type Ok = ();
     type Error = Error;
 
@@ -269,88 +703,82 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        self.writing_key = true;
-        let result = key.serialize(&mut **self);
-        self.writing_key = false;
-        result?;
-        self.writing_value = true;
-        let result = value.serialize(&mut **self);
-        self.writing_value = false;
-        result
+        self.serialize_entry_key(key)?;
+        self.serialize_entry_value(value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_entries()
     }
 }
 
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<O: Output> ser::SerializeSeq for &mut Serializer<O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> std::prelude::v1::Result<(), Self::Error>
-    where T: Serialize {
+    fn serialize_element<T>(&mut self, _value: &T) -> core::result::Result<(), Self::Error>
+    where T: ?Sized + Serialize {
         unimplemented!()
     }
 
-    fn end(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn end(self) -> core::result::Result<Self::Ok, Self::Error> {
         unimplemented!()
     }
 }
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<O: Output> ser::SerializeTuple for &mut Serializer<O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_element<T: ?Sized>(&mut self, _value: &T) -> std::prelude::v1::Result<(), Self::Error>
-    where T: Serialize {
+    fn serialize_element<T>(&mut self, _value: &T) -> core::result::Result<(), Self::Error>
+    where T: ?Sized + Serialize {
         unimplemented!()
     }
 
-    fn end(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn end(self) -> core::result::Result<Self::Ok, Self::Error> {
         unimplemented!()
     }
 }
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<O: Output> ser::SerializeTupleStruct for &mut Serializer<O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> std::prelude::v1::Result<(), Self::Error>
-    where T: Serialize {
+    fn serialize_field<T>(&mut self, _value: &T) -> core::result::Result<(), Self::Error>
+    where T: ?Sized + Serialize {
         unimplemented!()
     }
 
-    fn end(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn end(self) -> core::result::Result<Self::Ok, Self::Error> {
         unimplemented!()
     }
 }
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<O: Output> ser::SerializeStructVariant for &mut Serializer<O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(
+    fn serialize_field<T>(
         &mut self,
         _key: &'static str,
         _value: &T,
-    ) -> std::prelude::v1::Result<(), Self::Error>
-    where T: Serialize {
+    ) -> core::result::Result<(), Self::Error>
+    where T: ?Sized + Serialize {
         unimplemented!()
     }
 
-    fn end(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn end(self) -> core::result::Result<Self::Ok, Self::Error> {
         unimplemented!()
     }
 }
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<O: Output> ser::SerializeTupleVariant for &mut Serializer<O> {
     type Ok = ();
     type Error = Error;
 
-    fn serialize_field<T: ?Sized>(&mut self, _value: &T) -> std::prelude::v1::Result<(), Self::Error>
-    where T: Serialize {
+    fn serialize_field<T>(&mut self, _value: &T) -> core::result::Result<(), Self::Error>
+    where T: ?Sized + Serialize {
         unimplemented!()
     }
 
-    fn end(self) -> std::prelude::v1::Result<Self::Ok, Self::Error> {
+    fn end(self) -> core::result::Result<Self::Ok, Self::Error> {
         unimplemented!()
     }
 }
\ No newline at end of file
@@ -0,0 +1,141 @@
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::{Serialize, Serializer};
+
+/// The struct name `Deserializer` recognizes to hand out spans instead of just a value.
+pub(crate) const NAME: &str = "$__cstc_private_Spanned";
+pub(crate) const FIELDS: &[&str] = &[
+    "$__cstc_private_key_start",
+    "$__cstc_private_key_end",
+    "$__cstc_private_value_start",
+    "$__cstc_private_value_end",
+    "$__cstc_private_value",
+];
+
+/// A table value along with where its entry is in the input, for pointing at the exact bytes of a value.
+///
+/// Only `from_bytes`, `TableView::get_as` and the other functions reading a table from bytes know
+/// where values are; other deserializers can't produce a `Spanned`. A nested table's span covers all
+/// of it, header included. A field filled in by `construct_defaults` isn't in the input, so both of its
+/// spans are `0..0`. Serializing writes just the value, and comparisons ignore the spans.
+///
+/// ```
+/// use serde_construct_classic::{from_bytes, table, to_bytes, Spanned};
+///
+/// #[derive(serde_derive::Deserialize)]
+/// struct Level {
+///     speed: Spanned<f64>,
+/// }
+///
+/// let bytes = to_bytes(&table! { "speed" => 2.5 }).unwrap();
+/// let level: Level = from_bytes(&bytes).unwrap();
+/// assert_eq!(*level.speed.get_ref(), 2.5);
+/// assert_eq!(level.speed.key_span(), 10..20);
+/// assert_eq!(level.speed.value_span(), 20..32);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    key_span: Range<usize>,
+    value_span: Range<usize>,
+    value: T,
+}
+
+impl<T> Spanned<T> {
+    /// The key's length prefix, text and NUL terminator.
+    pub fn key_span(&self) -> Range<usize> {
+        self.key_span.clone()
+    }
+
+    /// The value's type tag and payload.
+    pub fn value_span(&self) -> Range<usize> {
+        self.value_span.clone()
+    }
+
+    /// Where the value's type tag is. Its payload follows 4 bytes later.
+    pub fn tag_offset(&self) -> usize {
+        self.value_span.start
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+impl<T: Hash> Hash for Spanned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+impl<T: Serialize> Serialize for Spanned<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+struct SpannedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SpannedVisitor<T> {
+    type Value = Spanned<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value read from a table's bytes")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Spanned<T>, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut positions = [0; 4];
+        for (position, field) in positions.iter_mut().zip(FIELDS) {
+            expect_field(&mut map, field)?;
+            *position = map.next_value::<u64>()? as usize;
+        }
+        expect_field(&mut map, FIELDS[4])?;
+        let value = map.next_value()?;
+        let [key_start, key_end, value_start, value_end] = positions;
+        Ok(Spanned { key_span: key_start..key_end, value_span: value_start..value_end, value })
+    }
+}
+
+fn expect_field<'de, A>(map: &mut A, field: &str) -> Result<(), A::Error>
+where
+    A: MapAccess<'de>,
+{
+    match map.next_key::<&str>()? {
+        Some(key) if key == field => Ok(()),
+        _ => Err(de::Error::custom("a Spanned can only be read from a table's bytes")),
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spanned<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(NAME, FIELDS, SpannedVisitor(PhantomData))
+    }
+}
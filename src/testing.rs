@@ -0,0 +1,207 @@
+//! Comparing encoded tables in tests, with `assert_table_eq!` and `assert_table_matches!`.
+//!
+//! ```ignore
+//! assert_table_eq!(to_bytes(&save)?, expected_bytes);
+//! assert_table_matches!(actual, expected, ignore_order, float_tolerance = 1e-9, ignore_keys = ["saved_at"]);
+//! ```
+//!
+//! Failures list each key whose entries differ, with the type, value and offset on both sides.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use encoding_rs::WINDOWS_1252;
+
+use crate::de::{Deserializer, DeserializerOptions};
+use crate::error::ErrorWithOffset;
+use crate::registry::{PayloadLength, TypeRegistry};
+use crate::value::Value;
+
+/// How `compare_tables` decides whether two tables match. The default is an exact comparison.
+#[derive(Debug, Clone, Default)]
+pub struct TableComparison {
+    /// Whether entries may appear in a different order.
+    pub ignore_order: bool,
+    /// How far apart floats may be and still match. At zero, floats must be identical,
+    /// including the sign of zero, though NaN matches NaN.
+    pub float_tolerance: f64,
+    /// Keys whose entries are left out of the comparison.
+    pub ignore_keys: Vec<String>,
+}
+
+impl TableComparison {
+    pub fn ignore_order(mut self) -> Self {
+        self.ignore_order = true;
+        self
+    }
+
+    pub fn float_tolerance(mut self, tolerance: f64) -> Self {
+        self.float_tolerance = tolerance;
+        self
+    }
+
+    pub fn ignore_keys<I>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.ignore_keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    fn values_match(&self, left: &Value, right: &Value) -> bool {
+        match (left, right) {
+            (Value::Float(a), Value::Float(b)) if a.is_nan() || b.is_nan() => a.is_nan() && b.is_nan(),
+            (Value::Float(a), Value::Float(b)) if self.float_tolerance == 0.0 => a.to_bits() == b.to_bits(),
+            // equal infinities are `inf - inf = NaN` apart
+            (Value::Float(a), Value::Float(b)) => a == b || (a - b).abs() <= self.float_tolerance,
+            // entries of nested tables are compared in order
+            (Value::Table(a), Value::Table(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|((ka, va), (kb, vb))| ka == kb && self.values_match(va, vb))
+            },
+            _ => left == right,
+        }
+    }
+}
+
+/// An entry of a decoded table, along with where it was found.
+struct Entry {
+    key: String,
+    value: Value,
+    /// The offset of the value's type tag.
+    offset: usize,
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.value {
+            Value::Int(v) => write!(f, "int {v}")?,
+            Value::Float(v) => write!(f, "float {v:?}")?,
+            Value::String(v) => write!(f, "string {v:?}")?,
+            Value::Raw(raw) => write!(f, "raw type {} ({} bytes)", raw.type_id, raw.payload.len())?,
+            Value::Table(table) => write!(f, "table of {} entries", table.len())?,
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+/// Decodes every entry, keeping values with unknown type tags as raw bytes.
+fn decode(table: &[u8]) -> Result<Vec<Entry>, ErrorWithOffset> {
+    let options = DeserializerOptions {
+        type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+        ..Default::default()
+    };
+    let mut deserializer = Deserializer::with_options(table, options.clone());
+    deserializer.read_header()?;
+    let mut entries = Vec::new();
+    while let Some(span) = deserializer.read_entry_span()? {
+        entries.push(Entry {
+            key: WINDOWS_1252.decode_without_bom_handling(span.key).0.into_owned(),
+            value: Value::from_encoded(&table[span.value_range.clone()], &options.type_registry),
+            offset: span.value_range.start,
+        });
+    }
+    Ok(entries)
+}
+
+/// How two tables differ, as found by `compare_tables`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDiff {
+    /// One line for each key whose entries differ.
+    pub lines: Vec<String>,
+}
+
+impl Display for TableDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tables differ at {} key(s):", self.lines.len())?;
+        for line in &self.lines {
+            write!(f, "\n  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two encoded tables entry by entry.
+///
+/// Entries are paired up by key, so the second entry with a key is compared with the second entry
+/// with that key on the other side. A table that can't be decoded is reported as the only difference.
+pub fn compare_tables(left: &[u8], right: &[u8], comparison: &TableComparison) -> Result<(), TableDiff> {
+    let (left, right) = match (decode(left), decode(right)) {
+        (Ok(left), Ok(right)) => (left, right),
+        (left, right) => {
+            let lines = [("left", left.err()), ("right", right.err())]
+                .into_iter()
+                .filter_map(|(side, err)| Some(alloc::format!("{side} table can't be decoded: {}", err?)))
+                .collect();
+            return Err(TableDiff { lines });
+        },
+    };
+    let compared = |entry: &&Entry| !comparison.ignore_keys.contains(&entry.key);
+    let left: Vec<&Entry> = left.iter().filter(compared).collect();
+    let right: Vec<&Entry> = right.iter().filter(compared).collect();
+
+    let mut lines = Vec::new();
+    let mut paired = alloc::vec![false; right.len()];
+    // the right-hand index and key of the last entry paired up, for reporting only entries that moved
+    let mut previous: Option<(usize, &str)> = None;
+    for (left_index, l) in left.iter().enumerate() {
+        let occurrence = left[..left_index].iter().filter(|other| other.key == l.key).count();
+        let pair = right.iter().enumerate().filter(|(_, r)| r.key == l.key).nth(occurrence);
+        let Some((right_index, r)) = pair else {
+            lines.push(alloc::format!("{:?}: only in left, {l}", l.key));
+            continue;
+        };
+        paired[right_index] = true;
+        if !comparison.values_match(&l.value, &r.value) {
+            lines.push(alloc::format!("{:?}: left {l}, right {r}", l.key));
+        } else if let Some((_, previous_key)) = previous.filter(|&(index, _)| right_index < index && !comparison.ignore_order) {
+            lines.push(alloc::format!("{:?}: after {previous_key:?} in left, before it in right", l.key));
+        }
+        previous = Some((right_index, &l.key));
+    }
+    for (r, _) in right.iter().zip(&paired).filter(|(_, paired)| !**paired) {
+        lines.push(alloc::format!("{:?}: only in right, {r}", r.key));
+    }
+    if lines.is_empty() {
+        Ok(())
+    } else {
+        Err(TableDiff { lines })
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_tables(left: &[u8], right: &[u8], comparison: &TableComparison) {
+    if let Err(diff) = compare_tables(left, right, comparison) {
+        panic!("{diff}");
+    }
+}
+
+/// Asserts that two encoded tables have the same entries in the same order.
+///
+/// Both sides can be anything that is `AsRef<[u8]>`, such as the `Vec<u8>` from `to_bytes`.
+/// Unlike comparing bytes, a failure lists the keys that differ.
+#[macro_export]
+macro_rules! assert_table_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_tables(
+            ::core::convert::AsRef::<[u8]>::as_ref(&$left),
+            ::core::convert::AsRef::<[u8]>::as_ref(&$right),
+            &$crate::testing::TableComparison::default(),
+        )
+    };
+}
+
+/// Asserts that two encoded tables match, with any `TableComparison` options after them:
+/// `ignore_order`, `float_tolerance = 1e-6` and `ignore_keys = ["saved_at"]`.
+#[macro_export]
+macro_rules! assert_table_matches {
+    ($left:expr, $right:expr $(, $option:ident $(= $value:expr)?)* $(,)?) => {
+        $crate::testing::assert_tables(
+            ::core::convert::AsRef::<[u8]>::as_ref(&$left),
+            ::core::convert::AsRef::<[u8]>::as_ref(&$right),
+            &$crate::testing::TableComparison::default() $(.$option($($value)?))*,
+        )
+    };
+}
@@ -0,0 +1,267 @@
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+/// How separators inside tokens are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// Tokens can't contain the separator, as with Construct's `tokenat`.
+    None,
+    /// A backslash makes the character after it part of the token.
+    Backslash,
+}
+
+/// How a list is packed into a string value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenOptions {
+    pub separator: char,
+    /// Whether whitespace around tokens is ignored when reading.
+    pub trim: bool,
+    pub escape: Escape,
+}
+
+/// A token that couldn't be read or written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenError {
+    /// The token's position in the list.
+    pub index: usize,
+    pub message: String,
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Token {}: {}", self.index, self.message)
+    }
+}
+
+impl TokenOptions {
+    /// Tokens separated by `separator`, untrimmed and unescaped.
+    pub const fn new(separator: char) -> Self {
+        TokenOptions { separator, trim: false, escape: Escape::None }
+    }
+
+    /// Splits `s` into tokens. An empty string has no tokens.
+    pub fn split<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        if s.is_empty() {
+            return Vec::new();
+        }
+        let mut tokens = Vec::new();
+        let mut start = 0;
+        let mut escaped = false;
+        let mut has_escapes = false;
+        for (i, c) in s.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if self.escape == Escape::Backslash && c == '\\' {
+                escaped = true;
+                has_escapes = true;
+            } else if c == self.separator {
+                tokens.push(self.token(&s[start..i], has_escapes));
+                start = i + c.len_utf8();
+                has_escapes = false;
+            }
+        }
+        tokens.push(self.token(&s[start..], has_escapes));
+        tokens
+    }
+
+    fn token<'s>(&self, raw: &'s str, has_escapes: bool) -> Cow<'s, str> {
+        let raw = if self.trim { raw.trim() } else { raw };
+        if !has_escapes {
+            return Cow::Borrowed(raw);
+        }
+        let mut token = String::with_capacity(raw.len());
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            token.push(if c == '\\' { chars.next().unwrap_or('\\') } else { c });
+        }
+        Cow::Owned(token)
+    }
+
+    /// Splits `s` and parses each token.
+    pub fn parse<T>(&self, s: &str) -> Result<Vec<T>, TokenError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.split(s).iter().enumerate()
+            .map(|(index, token)| {
+                token.parse().map_err(|e| TokenError { index, message: alloc::format!("{token:?} could not be parsed: {e}") })
+            })
+            .collect()
+    }
+
+    /// Joins the text of each item with the separator.
+    pub fn join<I>(&self, items: I) -> Result<String, TokenError>
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        let mut joined = String::new();
+        for (index, item) in items.into_iter().enumerate() {
+            if index > 0 {
+                joined.push(self.separator);
+            }
+            let token = item.to_string();
+            match self.escape {
+                Escape::None if token.contains(self.separator) => {
+                    let message = alloc::format!("{token:?} contains the separator {:?}", self.separator);
+                    return Err(TokenError { index, message });
+                },
+                Escape::None => joined.push_str(&token),
+                Escape::Backslash => {
+                    for c in token.chars() {
+                        if c == self.separator || c == '\\' {
+                            joined.push('\\');
+                        }
+                        joined.push(c);
+                    }
+                },
+            }
+        }
+        Ok(joined)
+    }
+}
+
+/// The `TokenOptions` a `Tokenized` field uses.
+///
+/// ```ignore
+/// struct Pipes;
+///
+/// impl TokenFormat for Pipes {
+///     const OPTIONS: TokenOptions = TokenOptions { trim: true, ..TokenOptions::new('|') };
+/// }
+/// ```
+pub trait TokenFormat {
+    const OPTIONS: TokenOptions;
+}
+
+/// Comma-separated tokens, as Construct's `tokenat` reads by default.
+pub struct Comma;
+
+impl TokenFormat for Comma {
+    const OPTIONS: TokenOptions = TokenOptions::new(',');
+}
+
+/// A list stored as one string value of tokens, such as `"sword,shield,potion"`.
+pub struct Tokenized<T, F = Comma> {
+    pub items: Vec<T>,
+    format: PhantomData<F>,
+}
+
+impl<T, F> Tokenized<T, F> {
+    pub fn new(items: Vec<T>) -> Self {
+        Tokenized { items, format: PhantomData }
+    }
+
+    pub fn into_inner(self) -> Vec<T> {
+        self.items
+    }
+}
+
+impl<T, F> From<Vec<T>> for Tokenized<T, F> {
+    fn from(items: Vec<T>) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<T, F> Default for Tokenized<T, F> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+impl<T, F> Deref for Tokenized<T, F> {
+    type Target = Vec<T>;
+
+    fn deref(&self) -> &Vec<T> {
+        &self.items
+    }
+}
+
+impl<T, F> DerefMut for Tokenized<T, F> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.items
+    }
+}
+
+impl<T: fmt::Debug, F> fmt::Debug for Tokenized<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.items.fmt(f)
+    }
+}
+
+impl<T: Clone, F> Clone for Tokenized<T, F> {
+    fn clone(&self) -> Self {
+        Self::new(self.items.clone())
+    }
+}
+
+impl<T: PartialEq, F> PartialEq for Tokenized<T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.items == other.items
+    }
+}
+
+impl<T: Display, F: TokenFormat> Serialize for Tokenized<T, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_tokens(&self.items, F::OPTIONS, serializer)
+    }
+}
+
+impl<'de, T, F> Deserialize<'de> for Tokenized<T, F>
+where
+    T: FromStr,
+    T::Err: Display,
+    F: TokenFormat,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_tokens(F::OPTIONS, deserializer).map(Self::new)
+    }
+}
+
+pub(crate) fn serialize_tokens<T, S>(items: &[T], options: TokenOptions, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    let joined = options.join(items).map_err(ser::Error::custom)?;
+    serializer.serialize_str(&joined)
+}
+
+pub(crate) fn deserialize_tokens<'de, T, D>(options: TokenOptions, deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_str(TokensVisitor { options, items: PhantomData })
+}
+
+struct TokensVisitor<T> {
+    options: TokenOptions,
+    items: PhantomData<T>,
+}
+
+impl<T> Visitor<'_> for TokensVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a string of tokens separated by {:?}", self.options.separator)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<T>, E> {
+        self.options.parse(v).map_err(E::custom)
+    }
+}
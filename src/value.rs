@@ -0,0 +1,446 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use encoding_rs::WINDOWS_1252;
+use serde::{de::{self, MapAccess, Visitor}, ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::constants::*;
+use crate::de::{from_bytes_with_options, DeserializerOptions};
+use crate::registry::TypeRegistry;
+
+/// Key under which a raw value's type tag appears when it is represented as a map.
+pub const RAW_TYPE_KEY: &str = "$type";
+/// Key under which a raw value's base64-encoded payload appears when it is represented as a map.
+pub const RAW_PAYLOAD_KEY: &str = "$raw";
+
+/// A value with a type tag this crate has no built-in decoding for, kept byte for byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawValue {
+    pub type_id: u32,
+    pub payload: Vec<u8>,
+}
+
+/// A single Hash Table value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Raw(RawValue),
+    /// A table stored in place of a value, which Construct itself can't read.
+    /// These are only written with `SerializerOptions::nested_tables`.
+    Table(HashTable),
+}
+
+impl Value {
+    /// Decodes a value from its type tag and payload, or from the bytes of a nested table, which must
+    /// already have been checked to be well-formed using `registry`.
+    pub(crate) fn from_encoded(bytes: &[u8], registry: &TypeRegistry) -> Value {
+        if bytes.starts_with(MAGIC) {
+            let options = DeserializerOptions { type_registry: registry.clone(), ..Default::default() };
+            let table = from_bytes_with_options(bytes, options).expect("nested tables are checked when they're skipped");
+            return Value::Table(table);
+        }
+        let (tag, payload) = bytes.split_at(4);
+        match u32::from_le_bytes(tag.try_into().unwrap()) {
+            TYPE_I64 => Value::Int(i64::from_le_bytes(payload.try_into().unwrap())),
+            TYPE_F64 => Value::Float(f64::from_le_bytes(payload.try_into().unwrap())),
+            TYPE_STRING => {
+                // skip the length prefix and the terminating NUL byte
+                let (s, _) = WINDOWS_1252.decode_without_bom_handling(&payload[4..payload.len() - 1]);
+                Value::String(s.into_owned())
+            },
+            type_id => Value::Raw(RawValue { type_id, payload: payload.to_vec() }),
+        }
+    }
+}
+
+/// A Hash Table with its entries in file order.
+/// Duplicate keys are kept as they appear, and lookups find the first of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HashTable {
+    entries: Vec<(String, Value)>,
+}
+
+impl HashTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Replaces the value of `key` in place, or appends a new entry if the key isn't present.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        let key = key.into();
+        let value = value.into();
+        match self.get_mut(&key) {
+            Some(slot) => Some(core::mem::replace(slot, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            },
+        }
+    }
+
+    /// Appends an entry without checking for an existing one with the same key.
+    pub fn push(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+        self.entries.push((key.into(), value.into()));
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl FromIterator<(String, Value)> for HashTable {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        HashTable { entries: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<(String, Value)> for HashTable {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, iter: I) {
+        self.entries.extend(iter)
+    }
+}
+
+impl IntoIterator for HashTable {
+    type Item = (String, Value);
+    type IntoIter = alloc::vec::IntoIter<(String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+/// Integers that fit in an `i64`, and `bool`, which is stored as `0` or `1`.
+macro_rules! impl_from_int {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Value {
+            fn from(v: $t) -> Self {
+                Value::Int(i64::from(v))
+            }
+        }
+    )*};
+}
+
+impl_from_int!(i8 i16 i32 u8 u16 u32 bool);
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::Float(f64::from(v))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(v.to_owned())
+    }
+}
+
+impl From<RawValue> for Value {
+    fn from(v: RawValue) -> Self {
+        Value::Raw(v)
+    }
+}
+
+impl From<HashTable> for Value {
+    fn from(v: HashTable) -> Self {
+        Value::Table(v)
+    }
+}
+
+/// Builds a `HashTable` from `key => value` pairs, in the order given.
+///
+/// Each value's type tag follows from its Rust type, the same way the serializer picks it:
+/// integers and `bool` are stored as integers, floats as floats, and text as strings.
+/// Integer literals are `i64`, so any value an integer entry can hold can be written without a suffix.
+/// Putting `int`, `float` or `string` before a value stores it with that tag instead.
+///
+/// Entries are expanded one at a time up to the last one that isn't a plain literal, so a table with
+/// more than about a hundred of those needs a higher `recursion_limit`; plain literals have no limit.
+///
+/// ```
+/// use serde_construct_classic::{table, to_bytes, Value};
+///
+/// let table = table! {
+///     "hp" => 100,
+///     "speed" => 2.5,
+///     "name" => "Bob",
+///     "x" => float 3,
+///     "level" => string 2,
+/// };
+/// assert_eq!(table.get("x"), Some(&Value::Float(3.0)));
+/// assert_eq!(table.get("level"), Some(&Value::from("2")));
+/// let bytes = to_bytes(&table).unwrap();
+/// ```
+#[macro_export]
+macro_rules! table {
+    // once only literals are left they're pushed from a single repetition, so tables of literals
+    // aren't limited by `recursion_limit`
+    (@push $table:ident; $($key:expr => $value:literal),* $(,)?) => {
+        $($table.push($key, $crate::__private::Literal::into_value($value));)*
+    };
+    (@push $table:ident; $key:expr => int $value:literal $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::Int($value));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => int $value:expr $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::Int(::core::convert::Into::<i64>::into($value)));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => float $value:literal $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::Float($crate::__private::FloatLiteral::into_f64($value)));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => float $value:expr $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::Float(::core::convert::Into::<f64>::into($value)));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => string $value:expr $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::String($crate::__private::ToString::to_string(&$value)));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => $value:literal $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::__private::Literal::into_value($value));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    (@push $table:ident; $key:expr => $value:expr $(, $($rest:tt)*)?) => {
+        $table.push($key, $crate::Value::from($value));
+        $crate::table!(@push $table; $($($rest)*)?);
+    };
+    ($($entries:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut table = $crate::HashTable::new();
+        $crate::table!(@push table; $($entries)*);
+        table
+    }};
+}
+
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry(RAW_TYPE_KEY, &self.type_id)?;
+        map.serialize_entry(RAW_PAYLOAD_KEY, &BASE64.encode(&self.payload))?;
+        map.end()
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Value::Int(v) => serializer.serialize_i64(*v),
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::Raw(v) => v.serialize(serializer),
+            Value::Table(v) => v.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for HashTable {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an integer, float, string, raw value or table")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Int(i64::from(v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        let v = i64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))?;
+        Ok(Value::Int(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    /// A map is a raw value if it has just the `$type` and `$raw` entries that one is written as,
+    /// and a nested table otherwise.
+    fn visit_map<A>(self, map: A) -> Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let table = HashTableVisitor.visit_map(map)?;
+        Ok(raw_entries(&table).map_or(Value::Table(table), Value::Raw))
+    }
+}
+
+/// The raw value `table` holds, if it is a `{"$type": N, "$raw": "<base64>"}` map.
+fn raw_entries(table: &HashTable) -> Option<RawValue> {
+    let [(first, _), (second, _)] = &table.entries[..] else { return None };
+    if first == second {
+        return None;
+    }
+    let Some(&Value::Int(type_id)) = table.get(RAW_TYPE_KEY) else { return None };
+    let Some(Value::String(payload)) = table.get(RAW_PAYLOAD_KEY) else { return None };
+    Some(RawValue { type_id: u32::try_from(type_id).ok()?, payload: BASE64.decode(payload).ok()? })
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a map with \"{RAW_TYPE_KEY}\" and \"{RAW_PAYLOAD_KEY}\" entries")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<RawValue, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut type_id = None;
+        let mut payload = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                RAW_TYPE_KEY => type_id = Some(map.next_value::<u32>()?),
+                RAW_PAYLOAD_KEY => {
+                    let encoded = map.next_value::<String>()?;
+                    let decoded = BASE64.decode(encoded).map_err(de::Error::custom)?;
+                    payload = Some(decoded);
+                },
+                _ => return Err(de::Error::unknown_field(&key, &[RAW_TYPE_KEY, RAW_PAYLOAD_KEY])),
+            }
+        }
+        let type_id = type_id.ok_or_else(|| de::Error::missing_field(RAW_TYPE_KEY))?;
+        let payload = payload.ok_or_else(|| de::Error::missing_field(RAW_PAYLOAD_KEY))?;
+        Ok(RawValue { type_id, payload })
+    }
+}
+
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(RawValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct HashTableVisitor;
+
+impl<'de> Visitor<'de> for HashTableVisitor {
+    type Value = HashTable;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hash table")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<HashTable, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry::<String, Value>()? {
+            entries.push(entry);
+        }
+        Ok(HashTable { entries })
+    }
+}
+
+impl<'de> Deserialize<'de> for HashTable {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(HashTableVisitor)
+    }
+}
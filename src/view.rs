@@ -0,0 +1,122 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use encoding_rs::WINDOWS_1252;
+use serde::Deserialize;
+
+use crate::de::{Deserializer, DeserializerOptions};
+use crate::error::ErrorWithOffset;
+use crate::value::Value;
+
+/// A read-only view of a Hash Table for looking up a few keys without deserializing the whole table.
+///
+/// Creating the view scans the table once to find where each entry is, without decoding any values;
+/// values are only decoded when asked for. Lookups find the first entry with a given key.
+pub struct TableView<'a> {
+    data: Data<'a>,
+    options: DeserializerOptions,
+    /// The stored text of each key, in file order.
+    keys: Vec<Range<usize>>,
+    /// The type tag and payload of each value, in file order.
+    values: Vec<Range<usize>>,
+    /// Entry indices ordered by key bytes, and by file order among equal keys.
+    index: Vec<usize>,
+}
+
+/// The table a view reads from.
+enum Data<'a> {
+    Borrowed(&'a [u8]),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Data<'_> {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Data::Borrowed(data) => data,
+            #[cfg(feature = "mmap")]
+            Data::Mapped(map) => map,
+        }
+    }
+}
+
+impl<'a> TableView<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, ErrorWithOffset> {
+        Self::with_options(data, DeserializerOptions::default())
+    }
+
+    /// Creates a view using the given options, which also apply to `get_as`.
+    pub fn with_options(data: &'a [u8], options: DeserializerOptions) -> Result<Self, ErrorWithOffset> {
+        Self::index(Data::Borrowed(data), options)
+    }
+
+    #[cfg(feature = "mmap")]
+    pub(crate) fn from_map(map: memmap2::Mmap, options: DeserializerOptions) -> Result<Self, ErrorWithOffset> {
+        Self::index(Data::Mapped(map), options)
+    }
+
+    fn index(data: Data<'a>, options: DeserializerOptions) -> Result<Self, ErrorWithOffset> {
+        let bytes = data.as_slice();
+        let mut deserializer = Deserializer::with_options(bytes, options.clone());
+        let key_count = deserializer.read_header()? as usize;
+        // the key count is only a hint, as it comes from the input
+        let mut keys = Vec::with_capacity(key_count.min(bytes.len() / 16));
+        let mut values = Vec::with_capacity(keys.capacity());
+        while let Some(span) = deserializer.read_entry_span()? {
+            // skip the length prefix and the terminating NUL byte
+            keys.push(span.key_range.start + 4..span.key_range.end - 1);
+            values.push(span.value_range);
+        }
+        let mut index: Vec<usize> = (0..keys.len()).collect();
+        index.sort_by(|&a, &b| bytes[keys[a].clone()].cmp(&bytes[keys[b].clone()]));
+        Ok(TableView { data, options, keys, values, index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The keys in file order.
+    pub fn keys(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        self.keys.iter().map(|range| WINDOWS_1252.decode_without_bom_handling(&self.data.as_slice()[range.clone()]).0)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let entry = self.find(key)?;
+        Some(Value::from_encoded(&self.data.as_slice()[self.values[entry].clone()], &self.options.type_registry))
+    }
+
+    /// Deserializes the value of `key`, with errors reporting offsets into the whole table.
+    pub fn get_as<'s, T>(&'s self, key: &str) -> Result<Option<T>, ErrorWithOffset>
+    where
+        T: Deserialize<'s>,
+    {
+        let Some(entry) = self.find(key) else { return Ok(None) };
+        let data = self.data.as_slice();
+        // with its length prefix and NUL terminator, for `Spanned`
+        let key_range = self.keys[entry].start - 4..self.keys[entry].end + 1;
+        let mut deserializer = Deserializer::for_value(data, key_range, self.values[entry].start, self.options.clone());
+        deserializer.read_with_defaults(|de| T::deserialize(de)).map(Some)
+    }
+
+    fn find(&self, key: &str) -> Option<usize> {
+        let (key, _, unmappable) = WINDOWS_1252.encode(key);
+        if unmappable {
+            return None;
+        }
+        let key = key.as_ref();
+        let data = self.data.as_slice();
+        let first = self.index.partition_point(|&entry| &data[self.keys[entry].clone()] < key);
+        let entry = *self.index.get(first)?;
+        (data[self.keys[entry].clone()] == *key).then_some(entry)
+    }
+}
@@ -0,0 +1,295 @@
+//! Adapters for `#[serde(with = "...")]` covering the ways Construct games commonly encode values.
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Settings {
+//!     #[serde(with = "serde_construct_classic::with::as_float")]
+//!     volume: u8,
+//!     #[serde(with = "serde_construct_classic::with::rgb")]
+//!     background: [u8; 3],
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::__private::{Float, Int};
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod dates;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use dates::*;
+
+/// Numbers and bools that can be stored under either numeric type tag.
+/// Conversions return `None` when the value doesn't fit the other type exactly.
+pub trait Number: Copy {
+    /// What `Deserialize` reports it expected when a value doesn't fit.
+    const EXPECTING: &'static str;
+    fn to_i64(self) -> Option<i64>;
+    fn to_f64(self) -> f64;
+    fn from_i64(v: i64) -> Option<Self>;
+    fn from_f64(v: f64) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ident)*) => {$(
+        impl Number for $ty {
+            const EXPECTING: &'static str = concat!("a value that fits in ", stringify!($ty));
+
+            fn to_i64(self) -> Option<i64> {
+                i64::try_from(self).ok()
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_i64(v: i64) -> Option<Self> {
+                Self::try_from(v).ok()
+            }
+
+            fn from_f64(v: f64) -> Option<Self> {
+                // the range check rules out NaN and infinities, and the round trip rules out fractions
+                if !(i128::MIN as f64..i128::MAX as f64).contains(&v) || v as i128 as f64 != v {
+                    return None;
+                }
+                Self::try_from(v as i128).ok()
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize);
+
+macro_rules! impl_float {
+    ($($ty:ident)*) => {$(
+        impl Number for $ty {
+            const EXPECTING: &'static str = "a number";
+
+            fn to_i64(self) -> Option<i64> {
+                let v = self as f64;
+                ((i64::MIN as f64..i64::MAX as f64).contains(&v) && v as i64 as f64 == v).then_some(v as i64)
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_i64(v: i64) -> Option<Self> {
+                Some(v as Self)
+            }
+
+            fn from_f64(v: f64) -> Option<Self> {
+                Some(v as Self)
+            }
+        }
+    )*};
+}
+
+impl_float!(f32 f64);
+
+impl Number for bool {
+    const EXPECTING: &'static str = "a number";
+
+    fn to_i64(self) -> Option<i64> {
+        Some(i64::from(self))
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(u8::from(self))
+    }
+
+    fn from_i64(v: i64) -> Option<Self> {
+        Some(v != 0)
+    }
+
+    fn from_f64(v: f64) -> Option<Self> {
+        Some(v != 0.0)
+    }
+}
+
+
+/// Stores a number or bool with the float type tag, and only reads values with that tag.
+pub mod as_float {
+    use super::*;
+
+    pub fn serialize<T: Number, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        Float(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Number, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        Float::deserialize(deserializer).map(|Float(v)| v)
+    }
+}
+
+/// Stores a number or bool with the integer type tag, and only reads values with that tag.
+/// Floats must be whole numbers to be written.
+pub mod as_int {
+    use super::*;
+
+    pub fn serialize<T: Number, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        Int(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Number, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        Int::deserialize(deserializer).map(|Int(v)| v)
+    }
+}
+
+/// Stores a bool as the integer 1 or 0, in every format. Any nonzero integer reads as true.
+pub mod bool_as_int {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        as_int::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        as_int::deserialize(deserializer)
+    }
+}
+
+/// Stores a bool as the string `"true"` or `"false"`.
+/// Reading also accepts `"1"` and `"0"`, and ignores ASCII case.
+pub mod bool_as_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(if *value { "true" } else { "false" })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        deserializer.deserialize_str(BoolVisitor)
+    }
+
+    struct BoolVisitor;
+
+    impl Visitor<'_> for BoolVisitor {
+        type Value = bool;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("\"true\", \"false\", \"1\" or \"0\"")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+            if v.eq_ignore_ascii_case("true") || v == "1" {
+                Ok(true)
+            } else if v.eq_ignore_ascii_case("false") || v == "0" {
+                Ok(false)
+            } else {
+                Err(E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+    }
+}
+
+/// Stores an `[r, g, b]` color as an integer the way Construct's `RGB()` packs it,
+/// `r + g * 256 + b * 65536`.
+pub mod rgb {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &[u8; 3], serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b] = value.map(i64::from);
+        serializer.serialize_i64(r | g << 8 | b << 16)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 3], D::Error> {
+        let packed: i64 = as_int::deserialize(deserializer)?;
+        if !(0..=0xFF_FFFF).contains(&packed) {
+            return Err(de::Error::invalid_value(de::Unexpected::Signed(packed), &"a color between 0 and 0xFFFFFF"));
+        }
+        let [r, g, b, _] = (packed as u32).to_le_bytes();
+        Ok([r, g, b])
+    }
+}
+
+/// Stores any serializable value as JSON text inside a string value.
+#[cfg(feature = "json")]
+pub mod json_in_string {
+    use super::*;
+    use alloc::string::String;
+    use serde::de::DeserializeOwned;
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(value).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+
+    pub fn deserialize<'de, T: DeserializeOwned, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        serde_json::from_str(&json).map_err(de::Error::custom)
+    }
+}
+
+/// Stores a list as comma-separated tokens in one string value, like `Tokenized<T>`.
+/// For other separators, use `Tokenized` with a `TokenFormat`.
+pub mod tokenized {
+    use super::*;
+    use crate::tokenized::{deserialize_tokens, serialize_tokens, Comma, TokenFormat};
+    use core::str::FromStr;
+
+    pub fn serialize<T: fmt::Display, S: Serializer>(value: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_tokens(value, Comma::OPTIONS, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        deserialize_tokens(Comma::OPTIONS, deserializer)
+    }
+}
+
+/// Stores rows of cells as lines of comma-separated cells in one string value, like `Grid<T>`.
+/// For other separators, use `Grid` with a `GridFormat`.
+pub mod grid {
+    use super::*;
+    use crate::grid::{deserialize_grid, serialize_grid, GridFormat, Lines};
+    use core::str::FromStr;
+
+    pub fn serialize<T: fmt::Display, S: Serializer>(value: &[Vec<T>], serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_grid(value, Lines::OPTIONS, serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<Vec<T>>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        deserialize_grid(Lines::OPTIONS, deserializer)
+    }
+}
+
+/// Stores bytes as a base64 string.
+pub mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<T: AsRef<[u8]>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(value.as_ref()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_str(Base64Visitor)
+    }
+
+    struct Base64Visitor;
+
+    impl Visitor<'_> for Base64Visitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a base64 string")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+            BASE64.decode(v).map_err(E::custom)
+        }
+    }
+}
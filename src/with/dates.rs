@@ -0,0 +1,316 @@
+use alloc::string::String;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{self, Serializer};
+
+use super::{as_float, as_int};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_DAY: i128 = 86_400 * NANOS_PER_SECOND;
+/// Days from the OLE Automation epoch, 1899-12-30, to the Unix epoch.
+const OLE_EPOCH_DAYS: f64 = 25_569.0;
+
+/// Dates, times and durations that `unix_seconds` and `ole_days` can store.
+pub trait TimeValue: Sized {
+    /// What `Deserialize` reports it expected when a value doesn't fit.
+    const EXPECTING: &'static str;
+    /// Whether this is a point in time counted from an epoch, rather than a length of time.
+    const IS_TIMESTAMP: bool;
+    /// Nanoseconds since the Unix epoch, or the length of a duration in nanoseconds.
+    /// Dates and times without an offset are taken to be in UTC.
+    fn to_nanos(&self) -> i128;
+    fn from_nanos(nanos: i128) -> Option<Self>;
+}
+
+/// Points in time that `iso8601` can store as text.
+pub trait TimeText: TimeValue {
+    fn to_text(&self) -> Option<String>;
+    fn from_text(s: &str) -> Option<Self>;
+}
+
+/// Stores a point in time as whole seconds since the Unix epoch, or a duration as whole seconds,
+/// with the integer type tag. Values with a fraction of a second can't be written.
+pub mod unix_seconds {
+    use super::*;
+
+    pub fn serialize<T: TimeValue, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let nanos = value.to_nanos();
+        if nanos % NANOS_PER_SECOND != 0 {
+            return Err(ser::Error::custom("value has a fraction of a second, which can't be stored in whole seconds"));
+        }
+        let seconds = i64::try_from(nanos / NANOS_PER_SECOND).map_err(|_| ser::Error::custom("value is out of range for an integer"))?;
+        serializer.serialize_i64(seconds)
+    }
+
+    pub fn deserialize<'de, T: TimeValue, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let seconds: i64 = as_int::deserialize(deserializer)?;
+        T::from_nanos(i128::from(seconds) * NANOS_PER_SECOND)
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Signed(seconds), &T::EXPECTING))
+    }
+}
+
+/// Stores a point in time as fractional days since the OLE Automation epoch (1899-12-30),
+/// as spreadsheets and Windows date values do, or a duration as fractional days,
+/// with the float type tag. Reading rounds to the nearest millisecond.
+pub mod ole_days {
+    use super::*;
+
+    pub fn serialize<T: TimeValue, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut days = value.to_nanos() as f64 / NANOS_PER_DAY as f64;
+        if T::IS_TIMESTAMP {
+            days += OLE_EPOCH_DAYS;
+        }
+        as_float::serialize(&days, serializer)
+    }
+
+    pub fn deserialize<'de, T: TimeValue, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let days: f64 = as_float::deserialize(deserializer)?;
+        let since_epoch = if T::IS_TIMESTAMP { days - OLE_EPOCH_DAYS } else { days };
+        let millis = since_epoch * 86_400_000.0;
+        // `f64::round` needs std, but casting truncates towards zero, so this rounds halves away from it
+        let millis = if millis < 0.0 { millis - 0.5 } else { millis + 0.5 };
+        // the range check also rules out NaN and infinities
+        (i64::MIN as f64..i64::MAX as f64).contains(&millis)
+            .then(|| T::from_nanos(i128::from(millis as i64) * 1_000_000))
+            .flatten()
+            .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Float(days), &T::EXPECTING))
+    }
+}
+
+/// Stores a point in time as ISO 8601 text: RFC 3339 for times with an offset, which are written in UTC,
+/// `2024-05-01T18:30:00` for times without one, and `2024-05-01` for dates.
+pub mod iso8601 {
+    use super::*;
+
+    pub fn serialize<T: TimeText, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = value.to_text().ok_or_else(|| ser::Error::custom("value can't be written as ISO 8601 text"))?;
+        serializer.serialize_str(&text)
+    }
+
+    pub fn deserialize<'de, T: TimeText, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        deserializer.deserialize_str(TextVisitor(PhantomData))
+    }
+
+    struct TextVisitor<T>(PhantomData<T>);
+
+    impl<T: TimeText> Visitor<'_> for TextVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} in ISO 8601 form", T::EXPECTING)
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            T::from_text(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
+
+/// Splits nanoseconds into seconds and the nanoseconds after them.
+fn split_nanos(nanos: i128) -> Option<(i64, u32)> {
+    let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).ok()?;
+    Some((seconds, nanos.rem_euclid(NANOS_PER_SECOND) as u32))
+}
+
+/// Nanoseconds since the Unix epoch for midnight at the start of a date, if `nanos` is one.
+fn whole_days(nanos: i128) -> Option<i128> {
+    (nanos % NANOS_PER_DAY == 0).then_some(nanos)
+}
+
+/// Appends the fraction of a second, if any, without trailing zeros.
+fn push_fraction(text: &mut String, nanos: u32) {
+    if nanos != 0 {
+        let digits = alloc::format!(".{nanos:09}");
+        text.push_str(digits.trim_end_matches('0'));
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::*;
+    use alloc::string::ToString;
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeDelta, Timelike, Utc};
+
+    impl TimeValue for DateTime<Utc> {
+        const EXPECTING: &'static str = "a date and time";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            i128::from(self.timestamp()) * NANOS_PER_SECOND + i128::from(self.timestamp_subsec_nanos())
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            let (seconds, nanos) = split_nanos(nanos)?;
+            DateTime::from_timestamp(seconds, nanos)
+        }
+    }
+
+    impl TimeText for DateTime<Utc> {
+        fn to_text(&self) -> Option<String> {
+            Some(self.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            DateTime::parse_from_rfc3339(s).ok().map(|time| time.with_timezone(&Utc))
+        }
+    }
+
+    impl TimeValue for NaiveDateTime {
+        const EXPECTING: &'static str = "a date and time";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            self.and_utc().to_nanos()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            DateTime::<Utc>::from_nanos(nanos).map(|time| time.naive_utc())
+        }
+    }
+
+    impl TimeText for NaiveDateTime {
+        fn to_text(&self) -> Option<String> {
+            let mut text = self.format("%Y-%m-%dT%H:%M:%S").to_string();
+            push_fraction(&mut text, self.nanosecond());
+            Some(text)
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()
+        }
+    }
+
+    impl TimeValue for NaiveDate {
+        const EXPECTING: &'static str = "a date";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            self.and_time(NaiveTime::MIN).to_nanos()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            NaiveDateTime::from_nanos(whole_days(nanos)?).map(|time| time.date())
+        }
+    }
+
+    impl TimeText for NaiveDate {
+        fn to_text(&self) -> Option<String> {
+            Some(self.format("%Y-%m-%d").to_string())
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+        }
+    }
+
+    impl TimeValue for TimeDelta {
+        const EXPECTING: &'static str = "a duration";
+        const IS_TIMESTAMP: bool = false;
+
+        fn to_nanos(&self) -> i128 {
+            // `subsec_nanos` has the same sign as the duration
+            i128::from(self.num_seconds()) * NANOS_PER_SECOND + i128::from(self.subsec_nanos())
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            let (seconds, nanos) = split_nanos(nanos)?;
+            TimeDelta::new(seconds, nanos)
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use super::*;
+    use time::format_description::well_known::Rfc3339;
+    use time::macros::format_description;
+    use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+    impl TimeValue for OffsetDateTime {
+        const EXPECTING: &'static str = "a date and time";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            self.unix_timestamp_nanos()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+        }
+    }
+
+    impl TimeText for OffsetDateTime {
+        fn to_text(&self) -> Option<String> {
+            self.to_offset(UtcOffset::UTC).format(&Rfc3339).ok()
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            OffsetDateTime::parse(s, &Rfc3339).ok().map(|time| time.to_offset(UtcOffset::UTC))
+        }
+    }
+
+    impl TimeValue for PrimitiveDateTime {
+        const EXPECTING: &'static str = "a date and time";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            self.assume_utc().unix_timestamp_nanos()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            OffsetDateTime::from_nanos(nanos).map(|time| PrimitiveDateTime::new(time.date(), time.time()))
+        }
+    }
+
+    impl TimeText for PrimitiveDateTime {
+        fn to_text(&self) -> Option<String> {
+            let mut text = self.format(format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]")).ok()?;
+            push_fraction(&mut text, self.nanosecond());
+            Some(text)
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            let format = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second][optional [.[subsecond]]]");
+            PrimitiveDateTime::parse(s, format).ok()
+        }
+    }
+
+    impl TimeValue for Date {
+        const EXPECTING: &'static str = "a date";
+        const IS_TIMESTAMP: bool = true;
+
+        fn to_nanos(&self) -> i128 {
+            self.midnight().to_nanos()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            PrimitiveDateTime::from_nanos(whole_days(nanos)?).map(PrimitiveDateTime::date)
+        }
+    }
+
+    impl TimeText for Date {
+        fn to_text(&self) -> Option<String> {
+            self.format(format_description!("[year]-[month]-[day]")).ok()
+        }
+
+        fn from_text(s: &str) -> Option<Self> {
+            Date::parse(s, format_description!("[year]-[month]-[day]")).ok()
+        }
+    }
+
+    impl TimeValue for Duration {
+        const EXPECTING: &'static str = "a duration";
+        const IS_TIMESTAMP: bool = false;
+
+        fn to_nanos(&self) -> i128 {
+            self.whole_nanoseconds()
+        }
+
+        fn from_nanos(nanos: i128) -> Option<Self> {
+            let (seconds, nanos) = split_nanos(nanos)?;
+            Duration::seconds(seconds).checked_add(Duration::nanoseconds(i64::from(nanos)))
+        }
+    }
+}
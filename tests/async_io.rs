@@ -0,0 +1,100 @@
+#![cfg(feature = "tokio")]
+
+use serde_construct_classic::{
+    from_async_reader, from_async_reader_with_options, table, to_async_writer, to_bytes, to_bytes_with_options, DeserializerOptions,
+    ErrorKind, HashTable, Limit, Limits, SerializerOptions, Value,
+};
+use serde_derive::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Save {
+    player: String,
+    level: u32,
+    playtime: f64,
+}
+
+#[tokio::test]
+async fn round_trip_over_a_pipe() {
+    let save = Save { player: "Bob".to_owned(), level: 4, playtime: 361.5 };
+    let (client, server) = tokio::io::duplex(16);
+    let write = async move { to_async_writer(client, &save).await };
+    let read = from_async_reader::<_, Save>(server);
+    let (written, read) = tokio::join!(write, read);
+    written.unwrap();
+    assert_eq!(read.unwrap(), Save { player: "Bob".to_owned(), level: 4, playtime: 361.5 });
+}
+
+#[tokio::test]
+async fn reading_stops_after_the_last_entry() {
+    let first = Save { player: "Bob".to_owned(), level: 4, playtime: 361.5 };
+    let second = Save { player: "Alice".to_owned(), level: 9, playtime: 12.0 };
+    let (mut client, mut server) = tokio::io::duplex(256);
+    to_async_writer(&mut client, &first).await.unwrap();
+    to_async_writer(&mut client, &second).await.unwrap();
+    // the writing end stays open, as it would for a socket
+    assert_eq!(from_async_reader::<_, Save>(&mut server).await.unwrap(), first);
+    assert_eq!(from_async_reader::<_, Save>(&mut server).await.unwrap(), second);
+    drop(client);
+}
+
+#[tokio::test]
+async fn errors_carry_offsets() {
+    let mut bytes = to_bytes(&Save { player: "Bob".to_owned(), level: 4, playtime: 361.5 }).unwrap();
+    bytes.truncate(bytes.len() - 1);
+    let err = from_async_reader::<_, HashTable>(bytes.as_slice()).await.unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::UnexpectedEnd));
+    assert_eq!(err.offset(), Some(bytes.len() - 7));
+}
+
+#[tokio::test]
+async fn byte_budget_stops_reading() {
+    let bytes = to_bytes(&Save { player: "Bob".to_owned(), level: 4, playtime: 361.5 }).unwrap();
+    let options = DeserializerOptions { limits: Limits { max_total_bytes: Some(20), ..Default::default() }, ..Default::default() };
+    let err = from_async_reader_with_options::<_, HashTable>(bytes.as_slice(), options).await.unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::TotalBytes, 20)));
+}
+
+#[tokio::test]
+async fn nested_tables_are_read_whole() {
+    let table = table! { "name" => "Bob", "inner" => table! { "x" => 7, "deeper" => table! {} }, "level" => 4 };
+    let bytes = to_bytes_with_options(&table, SerializerOptions { nested_tables: true, ..Default::default() }).unwrap();
+    let mut stream = bytes.clone();
+    stream.extend_from_slice(&bytes);
+    let mut reader = stream.as_slice();
+    assert_eq!(from_async_reader::<_, HashTable>(&mut reader).await.unwrap(), table);
+    let second: HashTable = from_async_reader(&mut reader).await.unwrap();
+    assert_eq!(second.get("inner"), Some(&Value::Table(table! { "x" => 7, "deeper" => table! {} })));
+    assert!(reader.is_empty());
+}
+
+/// A stream that fails the test if it is read from.
+struct Unread;
+
+impl AsyncRead for Unread {
+    fn poll_read(self: std::pin::Pin<&mut Self>, _: &mut std::task::Context<'_>, _: &mut ReadBuf<'_>) -> std::task::Poll<std::io::Result<()>> {
+        panic!("read past a length over the limit");
+    }
+}
+
+#[tokio::test]
+async fn lengths_over_the_limits_stop_reading() {
+    let limits = Limits { max_key_len: Some(8), max_string_len: Some(8), ..Default::default() };
+    let options = DeserializerOptions { limits, ..Default::default() };
+
+    // a string claiming to be 1 GiB long, with nothing of it sent yet
+    let mut string = to_bytes(&table! { "name" => "Bob" }).unwrap();
+    string.truncate(string.len() - 4);
+    let len_at = string.len() - 4;
+    string[len_at..].copy_from_slice(&(1u32 << 30).to_le_bytes());
+    let err = from_async_reader_with_options::<_, HashTable>(string.as_slice().chain(Unread), options.clone()).await.unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::StringLength, 8)));
+    assert_eq!(err.offset(), Some(len_at));
+
+    let mut key = to_bytes(&HashTable::new()).unwrap();
+    key[6..10].copy_from_slice(&1u32.to_le_bytes());
+    key.extend_from_slice(&(1u32 << 30).to_le_bytes());
+    let err = from_async_reader_with_options::<_, HashTable>(key.as_slice().chain(Unread), options).await.unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::KeyLength, 8)));
+    assert_eq!(err.offset(), Some(10));
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde_construct_classic::{from_bytes, table, to_bytes, to_bytes_with_options, HashTable, RawValue, SerializerOptions, Value};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("cstc_cli_{}_{name}", std::process::id()))
+}
+
+fn cstc_json(args: &[&PathBuf], command: &str) {
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json")).arg(command).args(args).status().unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn conversions_keep_entry_order() {
+    let mut table = HashTable::new();
+    table.push("zebra", 1);
+    table.push("apple", "two");
+    table.push("mango", 3.5);
+    table.push("blob", RawValue { type_id: 9, payload: vec![1, 2, 3] });
+    let bytes = to_bytes(&table).unwrap();
+    let (lvl, json, back) = (temp_path("in.lvl"), temp_path("out.json"), temp_path("back.lvl"));
+    fs::write(&lvl, &bytes).unwrap();
+
+    cstc_json(&[&lvl, &json], "tabletojson");
+    let text = fs::read_to_string(&json).unwrap();
+    let positions: Vec<usize> = ["zebra", "apple", "mango", "blob"].iter().map(|key| text.find(key).unwrap()).collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]), "{text}");
+
+    cstc_json(&[&json, &back], "jsontotable");
+    let round_tripped = fs::read(&back).unwrap();
+    assert_eq!(round_tripped, bytes);
+
+    for path in [lvl, json, back] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn expand_tokens_writes_arrays() {
+    let mut table = HashTable::new();
+    table.push("items", "sword,shield");
+    table.push("title", "Hello, world");
+    let (lvl, json) = (temp_path("tokens.lvl"), temp_path("tokens.json"));
+    fs::write(&lvl, to_bytes(&table).unwrap()).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json"))
+        .args(["tabletojson", "--expand-tokens", "items"])
+        .args([&lvl, &json])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let value: serde_json::Value = serde_json::from_str(&fs::read_to_string(&json).unwrap()).unwrap();
+    assert_eq!(value, serde_json::json!({"items": ["sword", "shield"], "title": "Hello, world"}));
+
+    for path in [lvl, json] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn nested_tables_need_the_flag() {
+    let (json, lvl) = (temp_path("nested.json"), temp_path("nested.lvl"));
+    fs::write(&json, r#"{"name": "Bob", "inner": {"x": 7}}"#).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json")).arg("jsontotable").args([&json, &lvl]).status().unwrap();
+    assert!(!status.success());
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json"))
+        .args(["jsontotable", "--nested-tables"])
+        .args([&json, &lvl])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let table: HashTable = from_bytes(&fs::read(&lvl).unwrap()).unwrap();
+    assert_eq!(table.get("inner"), Some(&Value::Table(table! { "x" => 7 })));
+
+    for path in [json, lvl] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn fmt_keeps_nested_tables() {
+    let table = table! { "zebra" => table! { "b" => 1, "a" => 2 }, "apple" => 3 };
+    let options = SerializerOptions { nested_tables: true, ..Default::default() };
+    let lvl = temp_path("fmt_nested.lvl");
+    fs::write(&lvl, to_bytes_with_options(&table, options).unwrap()).unwrap();
+
+    cstc_json(&[&lvl], "fmt");
+    let formatted: HashTable = from_bytes(&fs::read(&lvl).unwrap()).unwrap();
+    assert_eq!(formatted.keys().collect::<Vec<_>>(), ["apple", "zebra"]);
+    let Some(Value::Table(inner)) = formatted.get("zebra") else { panic!("{formatted:?}") };
+    assert_eq!(inner.keys().collect::<Vec<_>>(), ["a", "b"]);
+
+    fs::remove_file(lvl).unwrap();
+}
+
+#[test]
+fn failed_conversions_leave_the_output_alone() {
+    let (json, lvl) = (temp_path("broken.json"), temp_path("kept.lvl"));
+    fs::write(&json, r#"{"name": "Bob", "hp": "#).unwrap();
+    fs::write(&lvl, b"untouched").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json")).arg("jsontotable").args([&json, &lvl]).status().unwrap();
+    assert!(!status.success());
+    assert_eq!(fs::read(&lvl).unwrap(), b"untouched");
+
+    // a truncated table converted onto itself is kept as it was
+    let mut bytes = to_bytes(&table! { "name" => "Bob", "hp" => 100 }).unwrap();
+    bytes.truncate(bytes.len() - 3);
+    fs::write(&lvl, &bytes).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_cstc_json")).arg("tabletojson").args([&lvl, &lvl]).status().unwrap();
+    assert!(!status.success());
+    assert_eq!(fs::read(&lvl).unwrap(), bytes);
+
+    for path in [json, lvl] {
+        fs::remove_file(path).unwrap();
+    }
+}
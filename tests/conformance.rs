@@ -0,0 +1,240 @@
+//! Checks the synthetic tables in `tests/fixtures` against their golden JSON.
+//!
+//! The fixtures are defined in `valid_fixtures()` and `malformed_fixtures()` below. To regenerate them and their golden files after changing
+//! a definition or the crate's output, run `CSTC_BLESS=1 cargo test --test conformance` and review the diff.
+//!
+//! - `valid/NAME.bin` must read back as `valid/NAME.json`, and re-encode to the same bytes.
+//!   The JSON is what serde_json writes for a `HashTable`, so NaN and infinities appear as `null`.
+//! - `malformed/NAME.bin` must fail with the error and offset in `malformed/NAME.json`.
+
+mod support;
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_construct_classic::{from_bytes_with_options, to_bytes, DeserializerOptions, HashTable, PayloadLength, TypeRegistry};
+
+/// Builds table bytes entry by entry, including ones the serializer would never write.
+struct Builder(Vec<u8>);
+
+impl Builder {
+    /// A table whose header declares `count` keys.
+    fn new(count: u32) -> Self {
+        let mut bytes = b"MAP1.0".to_vec();
+        bytes.extend_from_slice(&count.to_le_bytes());
+        Builder(bytes)
+    }
+
+    fn u32(mut self, v: u32) -> Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    /// A length-prefixed, NUL-terminated string of Windows-1252 bytes.
+    fn text(self, text: &[u8]) -> Self {
+        self.u32(text.len() as u32 + 1).bytes(text).bytes(&[0])
+    }
+
+    fn int(self, key: &str, v: i64) -> Self {
+        self.text(key.as_bytes()).u32(0).bytes(&v.to_le_bytes())
+    }
+
+    fn float(self, key: &str, v: f64) -> Self {
+        self.text(key.as_bytes()).u32(1).bytes(&v.to_le_bytes())
+    }
+
+    fn string(self, key: &[u8], v: &[u8]) -> Self {
+        self.text(key).u32(2).text(v)
+    }
+
+    fn build(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Tag 3 has a 4-byte payload and tag 4 a length-prefixed one. Other unknown tags are errors.
+fn registry() -> TypeRegistry {
+    let mut registry = TypeRegistry::new();
+    registry.register(3, PayloadLength::Fixed(4));
+    registry.register(4, PayloadLength::Prefixed);
+    registry
+}
+
+fn valid_fixtures() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("empty", Builder::new(0).build()),
+        (
+            "ints",
+            Builder::new(6)
+                .int("zero", 0)
+                .int("one", 1)
+                .int("minus_one", -1)
+                .int("min", i64::MIN)
+                .int("max", i64::MAX)
+                .int("u32_max", u32::MAX.into())
+                .build(),
+        ),
+        (
+            "floats",
+            Builder::new(10)
+                .float("zero", 0.0)
+                .float("negative_zero", -0.0)
+                .float("half", 0.5)
+                .float("whole", 3.0)
+                .float("nan", f64::NAN)
+                .float("infinity", f64::INFINITY)
+                .float("negative_infinity", f64::NEG_INFINITY)
+                .float("max", f64::MAX)
+                .float("min_positive", f64::MIN_POSITIVE)
+                .float("subnormal", 5e-324)
+                .build(),
+        ),
+        ("nan_payload", Builder::new(1).float("nan", f64::from_bits(0x7FF8_0000_0000_0001)).build()),
+        (
+            "strings",
+            Builder::new(5)
+                .string(b"empty", b"")
+                .string(b"word", b"hello")
+                .string(b"spaces", b"  padded  ")
+                .string(b"newline", b"line\r\nbreak")
+                .string(b"number", b"1.5")
+                .build(),
+        ),
+        (
+            "windows_1252",
+            Builder::new(4)
+                .string(b"caf\xE9", b"na\xEFve")
+                .string(b"euro", b"\x80100")
+                .string(b"per_mille", b"\x89")
+                // undefined in Windows-1252, but decoded to U+0081 and encoded back
+                .string(b"undefined", b"\x81\x8D\x8F\x90\x9D")
+                .build(),
+        ),
+        ("mixed", Builder::new(3).int("hp", 100).float("speed", 2.5).string(b"name", b"Bob").build()),
+        ("duplicate_keys", Builder::new(3).int("a", 1).int("b", 2).int("a", 3).build()),
+        ("empty_key", Builder::new(1).int("", 7).build()),
+        ("numeric_keys", Builder::new(3).string(b"0", b"first").string(b"1", b"second").string(b"2", b"third").build()),
+        ("raw_fixed", Builder::new(2).text(b"color").u32(3).bytes(&[1, 2, 3, 4]).int("after", 5).build()),
+        ("raw_prefixed", Builder::new(2).text(b"blob").u32(4).u32(3).bytes(b"abc").int("after", 5).build()),
+    ]
+}
+
+fn malformed_fixtures() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("empty_file", Vec::new()),
+        ("bad_magic", b"MAP2.0\0\0\0\0".to_vec()),
+        ("truncated_header", b"MAP1.0\x01\0".to_vec()),
+        ("truncated_key", Builder::new(1).u32(10).bytes(b"ab").build()),
+        ("unterminated_key", Builder::new(1).u32(3).bytes(b"abc").u32(0).bytes(&[0; 8]).build()),
+        ("zero_length_key", Builder::new(1).u32(0).u32(0).bytes(&[0; 8]).build()),
+        ("missing_value", Builder::new(1).text(b"key").build()),
+        ("truncated_int", Builder::new(1).text(b"key").u32(0).bytes(&[1, 2, 3, 4]).build()),
+        ("truncated_float", Builder::new(1).text(b"key").u32(1).bytes(&[0; 7]).build()),
+        ("string_too_long", Builder::new(1).text(b"key").u32(2).u32(1000).bytes(b"short\0").build()),
+        ("unterminated_string", Builder::new(1).text(b"key").u32(2).u32(5).bytes(b"hello").build()),
+        ("unknown_type", Builder::new(1).text(b"key").u32(9).bytes(&[0; 8]).build()),
+        ("truncated_raw", Builder::new(1).text(b"key").u32(4).u32(10).bytes(b"abc").build()),
+    ]
+}
+
+fn fixtures_dir(kind: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(kind)
+}
+
+fn read(table: &[u8]) -> Result<HashTable, serde_construct_classic::ErrorWithOffset> {
+    from_bytes_with_options(table, DeserializerOptions { type_registry: registry(), ..Default::default() })
+}
+
+fn golden_valid(table: &[u8]) -> String {
+    let table = read(table).unwrap();
+    serde_json::to_string_pretty(&table).unwrap() + "\n"
+}
+
+fn golden_malformed(table: &[u8]) -> String {
+    let err = read(table).expect_err("malformed fixtures must not parse");
+    let golden = serde_json::json!({ "error": err.kind().to_string(), "offset": err.offset() });
+    serde_json::to_string_pretty(&golden).unwrap() + "\n"
+}
+
+/// Checks the committed files of one kind of fixture against their definitions and golden output,
+/// or rewrites them with `CSTC_BLESS` set.
+fn check_fixtures(kind: &str, fixtures: Vec<(&'static str, Vec<u8>)>, golden: fn(&[u8]) -> String) {
+    let dir = fixtures_dir(kind);
+    let bless = env::var_os("CSTC_BLESS").is_some();
+    if bless {
+        fs::create_dir_all(&dir).unwrap();
+    }
+    let mut failures = Vec::new();
+    let mut expected_files = BTreeSet::new();
+    for (name, bytes) in fixtures {
+        let bin_path = dir.join(format!("{name}.bin"));
+        let json_path = dir.join(format!("{name}.json"));
+        expected_files.insert(bin_path.clone());
+        expected_files.insert(json_path.clone());
+        let json = golden(&bytes);
+        if bless {
+            fs::write(&bin_path, &bytes).unwrap();
+            fs::write(&json_path, &json).unwrap();
+            continue;
+        }
+        let committed = fs::read(&bin_path).unwrap_or_default();
+        if let Some(report) = support::byte_diff_report(&bytes, &committed) {
+            failures.push(format!("{kind}/{name}.bin doesn't match its definition:\n{report}"));
+        }
+        let committed_json = fs::read_to_string(&json_path).unwrap_or_default();
+        if committed_json != json {
+            failures.push(format!("{kind}/{name}.json doesn't match the output:\n--- golden\n{committed_json}--- actual\n{json}"));
+        }
+    }
+    if !bless {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !expected_files.contains(&path) {
+                failures.push(format!("{} isn't a fixture", path.display()));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}\n\nRun with CSTC_BLESS=1 to regenerate the fixtures.", failures.join("\n\n"));
+}
+
+#[test]
+fn valid_fixtures_match_golden_json() {
+    check_fixtures("valid", valid_fixtures(), golden_valid);
+}
+
+#[test]
+fn malformed_fixtures_match_golden_errors() {
+    check_fixtures("malformed", malformed_fixtures(), golden_malformed);
+}
+
+#[test]
+fn valid_fixtures_round_trip() {
+    let mut failures = Vec::new();
+    for (name, bytes) in valid_fixtures() {
+        let written = to_bytes(&read(&bytes).unwrap()).unwrap();
+        if let Some(report) = support::byte_diff_report(&bytes, &written) {
+            failures.push(format!("valid/{name} changed in a round trip:\n{report}"));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn byte_diff_reports_locate_the_entry() {
+    let expected = valid_fixtures().into_iter().find(|(name, _)| *name == "mixed").unwrap().1;
+    let mut actual = expected.clone();
+    // the last byte of `speed`'s value
+    let offset = 10 + (4 + 3 + 12) + (4 + 6 + 12) - 1;
+    actual[offset] ^= 0xFF;
+    let report = support::byte_diff_report(&expected, &actual).unwrap();
+    assert!(report.contains(&format!("first difference at offset {offset} (0x{offset:x}), in the value of entry 1 (\"speed\")")), "{report}");
+    assert!(report.contains(&format!("[{:02x}]", actual[offset])), "{report}");
+    assert!(support::byte_diff_report(&expected, &expected).is_none());
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+use serde_derive::{Deserialize, Serialize};
+use serde_construct_classic::{from_bytes, from_value, to_bytes, to_value, ErrorKind, ErrorWithOffset, HashTable, RawValue, Value};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Player {
+    name: String,
+    level: u8,
+    speed: f32,
+    alive: bool,
+    grade: char,
+}
+
+fn player() -> Player {
+    Player { name: "Bob".into(), level: 3, speed: 2.5, alive: true, grade: 'A' }
+}
+
+#[test]
+fn to_value_uses_the_serializer_type_tags() {
+    let table = to_value(&player()).unwrap();
+    let entries: Vec<_> = table.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+    assert_eq!(entries, [
+        ("name".to_string(), Value::from("Bob")),
+        ("level".to_string(), Value::Int(3)),
+        ("speed".to_string(), Value::Float(2.5)),
+        ("alive".to_string(), Value::Int(1)),
+        ("grade".to_string(), Value::from("A")),
+    ]);
+    assert_eq!(to_bytes(&table).unwrap(), to_bytes(&player()).unwrap());
+}
+
+#[test]
+fn to_value_keys_and_errors() {
+    let numbered: BTreeMap<u32, &str> = [(1, "one"), (20, "twenty")].into_iter().collect();
+    let table = to_value(&numbered).unwrap();
+    assert_eq!(table.keys().collect::<Vec<_>>(), ["1", "20"]);
+
+    assert!(matches!(to_value(&vec![1, 2]).unwrap_err().kind(), ErrorKind::UnsupportedValue));
+    assert!(matches!(to_value(&5).unwrap_err().kind(), ErrorKind::UnsupportedValue));
+    let missing: BTreeMap<&str, Option<i32>> = [("some", Some(1)), ("none", None)].into_iter().collect();
+    assert!(matches!(to_value(&missing).unwrap_err().kind(), ErrorKind::UnsupportedValue));
+
+    let too_big: BTreeMap<&str, u64> = [("big", u64::MAX)].into_iter().collect();
+    assert!(matches!(to_value(&too_big).unwrap_err().kind(), ErrorKind::NumericOverflow));
+    let unencodable: BTreeMap<&str, &str> = [("text", "\u{3042}")].into_iter().collect();
+    assert!(matches!(to_value(&unencodable).unwrap_err().kind(), ErrorKind::TextEncodingError));
+}
+
+#[test]
+fn both_directions_share_an_error_type() {
+    fn round_trip(player: &Player) -> Result<Player, ErrorWithOffset> {
+        from_value(to_value(player)?)
+    }
+    assert_eq!(round_trip(&player()).unwrap(), player());
+    assert_eq!(to_value(&5).unwrap_err().offset(), None);
+}
+
+#[test]
+fn raw_values_keep_their_type_tag() {
+    let raw = RawValue { type_id: 9, payload: vec![1, 2, 3] };
+    let mut table = HashTable::new();
+    table.push("color", raw.clone());
+    let converted = to_value(&table).unwrap();
+    assert_eq!(converted, table);
+    let back: BTreeMap<String, RawValue> = from_value(converted).unwrap();
+    assert_eq!(back["color"], raw);
+}
+
+#[test]
+fn from_value_matches_from_bytes() {
+    let table = to_value(&player()).unwrap();
+    let from_table: Player = from_value(table.clone()).unwrap();
+    assert_eq!(from_table, player());
+    let bytes = to_bytes(&table).unwrap();
+    assert_eq!(from_bytes::<Player>(&bytes).unwrap(), from_table);
+
+    let json: serde_json::Value = from_value(table).unwrap();
+    assert_eq!(json["speed"], 2.5);
+    assert_eq!(json["alive"], 1);
+}
+
+#[test]
+fn from_value_checks_type_tags() {
+    let mut table = to_value(&player()).unwrap();
+    table.insert("level", 3.0);
+    let err = from_value::<Player>(table.clone()).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+    assert_eq!(err.key(), Some("level"));
+    assert_eq!(err.offset(), None);
+    assert_eq!(err.to_string(), "Key \"level\": TypeMismatch");
+
+    table.insert("level", 300);
+    let err = from_value::<Player>(table).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::NumericOverflow));
+}
+
+#[test]
+fn numeric_keys() {
+    let mut table = HashTable::new();
+    table.push("2", "two");
+    table.push("10", "ten");
+    let map: BTreeMap<u8, String> = from_value(table.clone()).unwrap();
+    assert_eq!(map[&10], "ten");
+
+    table.push("x", "not a number");
+    let err = from_value::<BTreeMap<u8, String>>(table).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::NonNumericKey(key) if key == "x"));
+}
+
+#[test]
+fn into_deserializer() {
+    assert_eq!(u16::deserialize(Value::Int(7).into_deserializer()).unwrap(), 7);
+    assert_eq!(String::deserialize(Value::from("hi").into_deserializer()).unwrap(), "hi");
+    assert!(f64::deserialize(Value::Int(7).into_deserializer()).is_err());
+    assert_eq!(Value::deserialize(Value::Float(0.5).into_deserializer()).unwrap(), Value::Float(0.5));
+
+    let table = to_value(&player()).unwrap();
+    assert_eq!(HashTable::deserialize(table.clone().into_deserializer()).unwrap(), table);
+}
@@ -0,0 +1,135 @@
+#![cfg(any(feature = "chrono", feature = "time"))]
+
+use serde_construct_classic::{from_bytes, to_bytes, HashTable, Value};
+
+fn table(entries: &[(&str, Value)]) -> HashTable {
+    entries.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+}
+
+#[cfg(feature = "chrono")]
+mod chrono {
+    use super::*;
+    use ::chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+    use serde_construct_classic::with;
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Save {
+        #[serde(with = "with::unix_seconds")]
+        last_played: DateTime<Utc>,
+        #[serde(with = "with::ole_days")]
+        created: DateTime<Utc>,
+        #[serde(with = "with::iso8601")]
+        birthday: NaiveDate,
+        #[serde(with = "with::ole_days")]
+        play_time: TimeDelta,
+    }
+
+    fn save() -> Save {
+        Save {
+            last_played: "2024-05-01T18:30:00Z".parse().unwrap(),
+            created: "2024-05-01T18:00:00Z".parse().unwrap(),
+            birthday: NaiveDate::from_ymd_opt(1990, 2, 28).unwrap(),
+            play_time: TimeDelta::hours(36),
+        }
+    }
+
+    #[test]
+    fn encodings() {
+        let save = save();
+        let bytes = to_bytes(&save).unwrap();
+        let expected = table(&[
+            ("last_played", Value::Int(1_714_588_200)),
+            ("created", Value::Float(45_413.75)),
+            ("birthday", Value::String("1990-02-28".into())),
+            ("play_time", Value::Float(1.5)),
+        ]);
+        assert_eq!(from_bytes::<HashTable>(&bytes).unwrap(), expected);
+        assert_eq!(from_bytes::<Save>(&bytes).unwrap(), save);
+    }
+
+    #[test]
+    fn ole_days_round_to_milliseconds() {
+        let bytes = to_bytes(&table(&[
+            ("last_played", Value::Int(0)),
+            ("created", Value::Float(25_569.0 + 1.0 / 86_400_000.0 * 0.9)),
+            ("birthday", Value::String("1970-01-01".into())),
+            ("play_time", Value::Float(-0.5)),
+        ]))
+        .unwrap();
+        let save: Save = from_bytes(&bytes).unwrap();
+        assert_eq!(save.created, DateTime::from_timestamp_millis(1).unwrap());
+        assert_eq!(save.play_time, TimeDelta::hours(-12));
+    }
+
+    #[test]
+    fn fractional_seconds_cannot_be_written_as_unix_seconds() {
+        let save = Save { last_played: "2024-05-01T18:30:00.5Z".parse().unwrap(), ..save() };
+        assert!(to_bytes(&save).is_err());
+    }
+
+    #[test]
+    fn errors_name_the_key_and_offset() {
+        let mut entries = from_bytes::<HashTable>(&to_bytes(&save()).unwrap()).unwrap();
+        entries.insert("birthday".to_string(), Value::String("28/02/1990".into()));
+        let bytes = to_bytes(&entries).unwrap();
+        let err = from_bytes::<Save>(&bytes).unwrap_err();
+        assert_eq!(err.key(), Some("birthday"));
+        let offset = err.offset().unwrap();
+        assert_eq!(&bytes[offset..offset + 4], &2u32.to_le_bytes());
+        assert!(err.to_string().starts_with(&format!("At offset {offset}, key \"birthday\": invalid value")), "{err}");
+    }
+}
+
+#[cfg(feature = "time")]
+mod time {
+    use super::*;
+    use ::time::macros::{date, datetime};
+    use ::time::{Date, Duration, OffsetDateTime, PrimitiveDateTime};
+    use serde_construct_classic::with;
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Save {
+        #[serde(with = "with::iso8601")]
+        last_played: OffsetDateTime,
+        #[serde(with = "with::iso8601")]
+        created: PrimitiveDateTime,
+        #[serde(with = "with::ole_days")]
+        birthday: Date,
+        #[serde(with = "with::unix_seconds")]
+        play_time: Duration,
+    }
+
+    #[test]
+    fn encodings() {
+        let save = Save {
+            last_played: datetime!(2024-05-01 20:30:00 +2),
+            created: datetime!(2024-05-01 18:00:00.25),
+            birthday: date!(1990-02-28),
+            play_time: Duration::hours(36),
+        };
+        let bytes = to_bytes(&save).unwrap();
+        let expected = table(&[
+            ("last_played", Value::String("2024-05-01T18:30:00Z".into())),
+            ("created", Value::String("2024-05-01T18:00:00.25".into())),
+            ("birthday", Value::Float(32_932.0)),
+            ("play_time", Value::Int(129_600)),
+        ]);
+        assert_eq!(from_bytes::<HashTable>(&bytes).unwrap(), expected);
+        assert_eq!(from_bytes::<Save>(&bytes).unwrap(), save);
+    }
+
+    #[test]
+    fn dates_must_be_whole_days() {
+        let bytes = to_bytes(&table(&[
+            ("last_played", Value::String("2024-05-01T18:30:00Z".into())),
+            ("created", Value::String("2024-05-01T18:00:00".into())),
+            ("birthday", Value::Float(32_932.5)),
+            ("play_time", Value::Int(0)),
+        ]))
+        .unwrap();
+        let err = from_bytes::<Save>(&bytes).unwrap_err();
+        assert_eq!(err.key(), Some("birthday"));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serde_construct_classic::{
+    from_bytes, from_bytes_with_options, to_bytes, to_bytes_with_options, DeserializerOptions, SerializerOptions, TableView,
+};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize)]
+struct Saved {
+    hp: i32,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Loaded {
+    name: String,
+    hp: i32,
+    speed: f64,
+    alive: bool,
+    title: Option<String>,
+}
+
+#[test]
+fn missing_fields_use_construct_defaults() {
+    let bytes = to_bytes(&Saved { hp: 30 }).unwrap();
+    assert!(from_bytes::<Loaded>(&bytes).is_err());
+
+    let options = DeserializerOptions { construct_defaults: true, ..Default::default() };
+    let loaded: Loaded = from_bytes_with_options(&bytes, options).unwrap();
+    assert_eq!(loaded, Loaded {
+        name: String::new(),
+        hp: 30,
+        speed: 0.0,
+        alive: false,
+        title: None,
+    });
+}
+
+fn options() -> DeserializerOptions {
+    DeserializerOptions { construct_defaults: true, ..Default::default() }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Inner {
+    x: i64,
+    label: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Typed {
+    hp: i32,
+    lives: u8,
+    inner: Inner,
+    flags: BTreeMap<String, i64>,
+    score: Option<i64>,
+}
+
+#[test]
+fn present_options_are_some() {
+    #[derive(Serialize)]
+    struct SavedTitle {
+        hp: i32,
+        title: &'static str,
+    }
+    let bytes = to_bytes(&SavedTitle { hp: 30, title: "Hero" }).unwrap();
+    let loaded: Loaded = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(loaded.title.as_deref(), Some("Hero"));
+}
+
+#[test]
+fn missing_fields_of_other_types() {
+    let bytes = to_bytes(&Saved { hp: 30 }).unwrap();
+    let typed: Typed = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(typed, Typed {
+        hp: 30,
+        lives: 0,
+        inner: Inner { x: 0, label: String::new() },
+        flags: BTreeMap::new(),
+        score: None,
+    });
+}
+
+#[test]
+fn missing_fields_of_nested_tables() {
+    #[derive(Serialize)]
+    struct SavedInner {
+        label: &'static str,
+    }
+    #[derive(Serialize)]
+    struct SavedOuter {
+        hp: i32,
+        inner: SavedInner,
+    }
+    let nested = SerializerOptions { nested_tables: true, ..Default::default() };
+    let bytes = to_bytes_with_options(&SavedOuter { hp: 30, inner: SavedInner { label: "door" } }, nested).unwrap();
+    assert!(from_bytes::<Typed>(&bytes).is_err());
+    let typed: Typed = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(typed.inner, Inner { x: 0, label: "door".into() });
+}
+
+#[test]
+fn construct_defaults_take_precedence_over_serde_defaults() {
+    fn full_health() -> i32 {
+        100
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct WithDefaults {
+        #[serde(default = "full_health")]
+        hp: i32,
+        #[serde(default)]
+        name: Option<String>,
+    }
+    let bytes = to_bytes(&BTreeMap::<String, i64>::new()).unwrap();
+    let plain: WithDefaults = from_bytes(&bytes).unwrap();
+    assert_eq!(plain, WithDefaults { hp: 100, name: None });
+    let defaulted: WithDefaults = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(defaulted, WithDefaults { hp: 0, name: None });
+}
+
+#[test]
+fn aliases_of_fields_that_were_read_get_no_default() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Renamed {
+        #[serde(alias = "old", alias = "older")]
+        hp: i32,
+        #[serde(alias = "alias")]
+        name: String,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Outer {
+        renamed: Renamed,
+        #[serde(alias = "lvl")]
+        level: i32,
+    }
+    let bytes = to_bytes(&Saved { hp: 5 }).unwrap();
+    let renamed: Renamed = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(renamed, Renamed { hp: 5, name: String::new() });
+
+    let bytes = to_bytes(&BTreeMap::from([("older", 5), ("lvl", 2)])).unwrap();
+    let renamed: Renamed = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(renamed, Renamed { hp: 5, name: String::new() });
+    // a struct that is missing altogether gets one default per field
+    let outer: Outer = from_bytes_with_options(&bytes, options()).unwrap();
+    assert_eq!(outer, Outer { renamed: Renamed { hp: 0, name: String::new() }, level: 2 });
+
+    let nested = SerializerOptions { nested_tables: true, ..Default::default() };
+    let bytes = to_bytes_with_options(&BTreeMap::from([("stats", BTreeMap::from([("old", 7)]))]), nested).unwrap();
+    let view = TableView::with_options(&bytes, options()).unwrap();
+    assert_eq!(view.get_as::<Renamed>("stats").unwrap(), Some(Renamed { hp: 7, name: String::new() }));
+}
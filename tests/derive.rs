@@ -0,0 +1,122 @@
+#![cfg(feature = "derive")]
+
+use serde_construct_classic::{from_bytes, from_bytes_with_options, to_bytes, ConstructTable, DeserializerOptions, ErrorKind, HashTable};
+
+#[derive(ConstructTable, Debug, PartialEq)]
+struct Player {
+    #[cstc(key = "Player name")]
+    name: String,
+    #[cstc(int)]
+    speed: f64,
+    #[cstc(float)]
+    lives: u8,
+    #[cstc(construct_bool)]
+    alive: bool,
+    #[cstc(construct_bool, float)]
+    visible: bool,
+    score: i64,
+    title: Option<String>,
+}
+
+fn player() -> Player {
+    Player { name: "Bob".into(), speed: 250.0, lives: 3, alive: true, visible: false, score: 10, title: None }
+}
+
+fn original() -> Vec<u8> {
+    let mut table = HashTable::new();
+    table.push("Player name", "Bob");
+    table.push("speed", 250);
+    table.push("lives", 3.0);
+    table.push("alive", 1);
+    table.push("visible", 0.0);
+    table.push("score", 10);
+    to_bytes(&table).unwrap()
+}
+
+#[test]
+fn hints_choose_the_written_tags() {
+    let mut player = player();
+    player.title = Some("Hero".into());
+    let mut expected = original();
+    let mut table: HashTable = from_bytes(&expected).unwrap();
+    table.push("title", "Hero");
+    expected = to_bytes(&table).unwrap();
+    assert_eq!(to_bytes(&player).unwrap(), expected);
+}
+
+#[test]
+fn present_options_round_trip() {
+    let mut player = player();
+    player.title = Some("Hero".into());
+    let bytes = to_bytes(&player).unwrap();
+    assert_eq!(from_bytes::<Player>(&bytes).unwrap(), player);
+}
+
+#[test]
+fn hinted_struct_reproduces_the_original_file() {
+    let bytes = original();
+    let player: Player = from_bytes(&bytes).unwrap();
+    assert_eq!(player, self::player());
+    assert_eq!(to_bytes(&player).unwrap(), bytes);
+}
+
+#[test]
+fn hints_restrict_the_accepted_tags() {
+    let mut table: HashTable = from_bytes(&original()).unwrap();
+    table.insert("speed", 250.0);
+    let err = from_bytes::<Player>(&to_bytes(&table).unwrap()).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+}
+
+#[test]
+fn floats_must_fit_the_field() {
+    let mut table: HashTable = from_bytes(&original()).unwrap();
+    table.insert("lives", 2.5);
+    assert!(from_bytes::<Player>(&to_bytes(&table).unwrap()).is_err());
+    table.insert("lives", 300.0);
+    assert!(from_bytes::<Player>(&to_bytes(&table).unwrap()).is_err());
+}
+
+#[test]
+fn unknown_and_duplicate_keys() {
+    let mut table: HashTable = from_bytes(&original()).unwrap();
+    table.push("score", 99);
+    table.push("unknown", "ignored");
+    let player: Player = from_bytes(&to_bytes(&table).unwrap()).unwrap();
+    assert_eq!(player.score, 10);
+}
+
+#[test]
+fn missing_fields() {
+    let mut table: HashTable = from_bytes(&original()).unwrap();
+    table.remove("lives");
+    let bytes = to_bytes(&table).unwrap();
+    let err = from_bytes::<Player>(&bytes).unwrap_err();
+    assert!(err.to_string().contains("missing field `lives`"), "{err}");
+
+    let options = DeserializerOptions { construct_defaults: true, ..Default::default() };
+    let player: Player = from_bytes_with_options(&bytes, options).unwrap();
+    assert_eq!(player.lives, 0);
+}
+
+#[test]
+fn hints_also_apply_to_other_formats() {
+    let json = serde_json::to_value(player()).unwrap();
+    assert_eq!(json["speed"], serde_json::json!(250));
+    assert_eq!(json["lives"], serde_json::json!(3.0));
+    assert_eq!(json["alive"], serde_json::json!(1));
+    let player: Player = serde_json::from_value(json).unwrap();
+    assert_eq!(player, self::player());
+}
+
+#[derive(ConstructTable, Debug, PartialEq)]
+struct Generic<T> {
+    #[cstc(key = "value")]
+    inner: T,
+}
+
+#[test]
+fn generic_structs() {
+    let bytes = to_bytes(&Generic { inner: 5i64 }).unwrap();
+    assert_eq!(from_bytes::<Generic<i64>>(&bytes).unwrap(), Generic { inner: 5 });
+}
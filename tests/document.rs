@@ -0,0 +1,56 @@
+use serde_construct_classic::{from_bytes_with_options, to_bytes, Document, DeserializerOptions, HashTable, PayloadLength, RawValue, TypeRegistry, Value};
+
+fn lenient() -> DeserializerOptions {
+    DeserializerOptions {
+        type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+        ..Default::default()
+    }
+}
+
+fn original() -> Vec<u8> {
+    let mut table = HashTable::new();
+    table.push("name", "Bob");
+    table.push("hp", 100);
+    table.push("hp", 50);
+    table.push("blob", RawValue { type_id: 9, payload: vec![1, 2, 3] });
+    table.push("speed", 2.5);
+    to_bytes(&table).unwrap()
+}
+
+#[test]
+fn untouched_document_is_identical() {
+    let bytes = original();
+    let document = Document::parse_with_options(bytes.clone(), lenient()).unwrap();
+    assert_eq!(document.len(), 5);
+    assert_eq!(document.to_bytes(), bytes);
+}
+
+#[test]
+fn set_only_changes_that_value() {
+    let bytes = original();
+    let mut document = Document::parse_with_options(bytes.clone(), lenient()).unwrap();
+    assert_eq!(document.set("hp", 75).unwrap(), Some(Value::Int(100)));
+    let edited = document.to_bytes();
+    assert_eq!(edited.len(), bytes.len());
+    let differing: Vec<usize> = (0..bytes.len()).filter(|&i| bytes[i] != edited[i]).collect();
+    assert_eq!(differing, [42]);
+
+    let table: HashTable = from_bytes_with_options(&edited, lenient()).unwrap();
+    let hp: Vec<&Value> = table.iter().filter(|(k, _)| *k == "hp").map(|(_, v)| v).collect();
+    assert_eq!(hp, [&Value::Int(75), &Value::Int(50)]);
+}
+
+#[test]
+fn insert_and_remove() {
+    let mut document = Document::parse_with_options(original(), lenient()).unwrap();
+    assert!(!document.insert("name", "Alice").unwrap());
+    assert!(document.insert("title", "Knight").unwrap());
+    assert_eq!(document.remove("name"), Some(Value::String("Bob".to_owned())));
+    assert_eq!(document.remove("missing"), None);
+
+    let edited = document.to_bytes();
+    assert_eq!(u32::from_le_bytes(edited[6..10].try_into().unwrap()), 5);
+    let table: HashTable = from_bytes_with_options(&edited, lenient()).unwrap();
+    assert_eq!(table.keys().collect::<Vec<_>>(), ["hp", "hp", "blob", "speed", "title"]);
+    assert_eq!(table.get("blob"), Some(&Value::Raw(RawValue { type_id: 9, payload: vec![1, 2, 3] })));
+}
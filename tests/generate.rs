@@ -0,0 +1,158 @@
+#![cfg(any(feature = "arbitrary", feature = "proptest"))]
+
+use serde_construct_classic::{
+    from_bytes_with_options, to_bytes, DeserializerOptions, GeneratedTable, GeneratorOptions, HashTable, Value, ValueWeights,
+};
+
+/// Checks that `generated` reads back and re-encodes to the same bytes.
+fn check_round_trip(generated: &GeneratedTable, options: &GeneratorOptions) {
+    let options = DeserializerOptions { type_registry: options.type_registry(), ..Default::default() };
+    let table: HashTable = from_bytes_with_options(&generated.bytes, options).unwrap();
+    assert_eq!(table.len(), generated.table.len());
+    assert_eq!(to_bytes(&table).unwrap(), generated.bytes);
+}
+
+fn raw_options() -> GeneratorOptions {
+    GeneratorOptions { weights: ValueWeights { raw: 1, ..Default::default() }, ..Default::default() }
+}
+
+/// Weights whose sum doesn't fit in a `u32`.
+fn heavy_options() -> GeneratorOptions {
+    GeneratorOptions { weights: ValueWeights { int: u32::MAX, float: u32::MAX, string: u32::MAX, raw: 1 }, ..Default::default() }
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary {
+    use super::*;
+    use ::arbitrary::{Arbitrary, Unstructured};
+
+    /// Deterministic input bytes for `Unstructured`.
+    fn noise(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_tables_round_trip() {
+        for seed in 0..200 {
+            let data = noise(seed, 4096);
+            let generated = GeneratedTable::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            check_round_trip(&generated, &GeneratorOptions::default());
+
+            let options = raw_options();
+            let table = options.arbitrary_table(&mut Unstructured::new(&data)).unwrap();
+            check_round_trip(&GeneratedTable::from(table), &options);
+        }
+    }
+
+    #[test]
+    fn edge_cases_can_be_turned_off() {
+        let options = GeneratorOptions { edge_case_percent: 0, ..Default::default() };
+        for seed in 0..50 {
+            let table = options.arbitrary_table(&mut Unstructured::new(&noise(seed, 1024))).unwrap();
+            check_round_trip(&GeneratedTable::from(table), &options);
+        }
+    }
+
+    #[test]
+    fn weights_can_be_large() {
+        let options = heavy_options();
+        let mut kinds = [false; 3];
+        for seed in 0..50 {
+            let table = options.arbitrary_table(&mut Unstructured::new(&noise(seed, 1024))).unwrap();
+            for value in table.values() {
+                match value {
+                    Value::Int(_) => kinds[0] = true,
+                    Value::Float(_) => kinds[1] = true,
+                    Value::String(_) => kinds[2] = true,
+                    _ => {},
+                }
+            }
+            check_round_trip(&GeneratedTable::from(table), &options);
+        }
+        assert_eq!(kinds, [true; 3]);
+    }
+
+    #[test]
+    fn keys_come_from_the_pool() {
+        let options = GeneratorOptions { keys: vec!["hp".into(), "name".into()], ..Default::default() };
+        for seed in 0..50 {
+            let table = options.arbitrary_table(&mut Unstructured::new(&noise(seed, 1024))).unwrap();
+            assert!(table.len() <= 2);
+            assert!(table.keys().all(|key| key == "hp" || key == "name"));
+        }
+    }
+}
+
+#[cfg(feature = "proptest")]
+mod proptest {
+    use super::*;
+    use ::proptest::prelude::*;
+    use serde_construct_classic::{from_bytes, Charset};
+
+    proptest! {
+        #[test]
+        fn generated_tables_round_trip(generated in any::<GeneratedTable>()) {
+            check_round_trip(&generated, &GeneratorOptions::default());
+        }
+
+        #[test]
+        fn raw_values_round_trip(generated in any_with::<GeneratedTable>(raw_options())) {
+            check_round_trip(&generated, &raw_options());
+        }
+
+        #[test]
+        fn options_shape_the_table(table in any_with::<HashTable>(GeneratorOptions {
+            keys: vec!["a".into(), "b".into()],
+            duplicate_keys: true,
+            max_entries: 5,
+            charset: Charset::Ascii,
+            weights: ValueWeights { int: 0, float: 0, string: 1, raw: 0 },
+            ..Default::default()
+        })) {
+            prop_assert!(table.len() <= 5);
+            prop_assert!(table.keys().all(|key| key == "a" || key == "b"));
+            for value in table.values() {
+                prop_assert!(matches!(value, Value::String(s) if s.is_ascii()));
+            }
+        }
+
+        #[test]
+        fn weights_can_be_large(generated in any_with::<GeneratedTable>(heavy_options())) {
+            check_round_trip(&generated, &heavy_options());
+        }
+
+        #[test]
+        fn edge_cases(value in any_with::<Value>(GeneratorOptions {
+            edge_case_percent: 100,
+            weights: ValueWeights { int: 1, float: 1, string: 0, raw: 0 },
+            ..Default::default()
+        })) {
+            match value {
+                Value::Int(v) => prop_assert!([0, 1, -1, i64::MIN, i64::MAX].contains(&v) || v.unsigned_abs() >= i32::MAX as u64),
+                Value::Float(v) => prop_assert!(v.is_nan() || v == 0.0 || v.is_infinite() || v.abs() >= f64::MAX || v.abs() <= f64::EPSILON),
+                other => prop_assert!(false, "{other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn edge_case_bytes_read_back() {
+        let table: HashTable =
+            [("nan", Value::Float(f64::NAN)), ("neg_zero", Value::Float(-0.0)), ("min", Value::Int(i64::MIN)), ("empty", Value::from(""))]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect();
+        let generated = GeneratedTable::from(table);
+        check_round_trip(&generated, &GeneratorOptions::default());
+        let read: HashTable = from_bytes(&generated.bytes).unwrap();
+        assert!(matches!(read.get("neg_zero"), Some(Value::Float(v)) if v.is_sign_negative()));
+    }
+}
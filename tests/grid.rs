@@ -0,0 +1,96 @@
+use serde_construct_classic::{from_bytes, to_bytes, with, Grid, GridFormat, GridOptions, HashTable, Value};
+use serde_derive::{Deserialize, Serialize};
+
+struct Tiles;
+
+impl GridFormat for Tiles {
+    const OPTIONS: GridOptions = GridOptions { terminated: true, ..GridOptions::new(";", " ") };
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Level {
+    #[serde(with = "with::grid")]
+    collision: Vec<Vec<u8>>,
+    tiles: Grid<u16, Tiles>,
+}
+
+fn table(entries: &[(&str, &str)]) -> Vec<u8> {
+    let table: HashTable = entries.iter().map(|(key, value)| (key.to_string(), Value::from(*value))).collect();
+    to_bytes(&table).unwrap()
+}
+
+#[test]
+fn grids_round_trip_through_strings() {
+    let bytes = table(&[("collision", "0,1,1\n1,0,0"), ("tiles", "10 11;12 13;14 15;")]);
+    let level: Level = from_bytes(&bytes).unwrap();
+    assert_eq!(level.collision, [[0, 1, 1], [1, 0, 0]]);
+    assert_eq!((level.tiles.width(), level.tiles.height()), (2, 3));
+    assert_eq!(level.tiles.get(1, 2), Some(&15));
+    assert_eq!(level.tiles.get(2, 0), None);
+    assert_eq!(level.tiles.get(0, usize::MAX), None);
+    assert_eq!(to_bytes(&level).unwrap(), bytes);
+}
+
+#[test]
+fn empty_strings_are_empty_grids() {
+    let level: Level = from_bytes(&table(&[("collision", ""), ("tiles", "")])).unwrap();
+    assert!(level.collision.is_empty());
+    assert_eq!((level.tiles.width(), level.tiles.height()), (0, 0));
+}
+
+#[test]
+fn ragged_rows_name_the_row_and_column() {
+    let err = from_bytes::<Level>(&table(&[("collision", "0,1,1\n1,0,0\n1,0"), ("tiles", "")])).unwrap_err();
+    assert!(err.to_string().contains("Row 2, column 2: expected 3 cells like the first row, found 2"), "{err}");
+
+    let err = Grid::<u8>::from_rows(vec![vec![1], vec![2, 3]]).unwrap_err();
+    assert_eq!((err.row, err.column), (1, 1));
+
+    let level = Level { collision: vec![vec![0, 1], vec![1]], tiles: Grid::new(1, 1, 0) };
+    assert!(to_bytes(&level).unwrap_err().to_string().contains("Row 1, column 1"));
+}
+
+#[test]
+fn parse_errors_name_the_cell() {
+    let err = from_bytes::<Level>(&table(&[("collision", ""), ("tiles", "1 2;3 x;")])).unwrap_err();
+    assert!(err.to_string().contains("Row 1, column 1: \"x\" could not be parsed"), "{err}");
+}
+
+#[test]
+fn terminated_grids_need_the_last_separator() {
+    let err = from_bytes::<Level>(&table(&[("collision", ""), ("tiles", "1 2;3 4")])).unwrap_err();
+    assert!(err.to_string().contains("Row 1, column 0: the last row isn't terminated"), "{err}");
+}
+
+#[test]
+fn options_split_and_join() {
+    let options = GridOptions::new("\r\n", "|");
+    assert_eq!(options.split("a|b\r\nc|d").unwrap(), [["a", "b"], ["c", "d"]]);
+    assert_eq!(options.join(&[vec![1, 2], vec![3, 4]]).unwrap(), "1|2\r\n3|4");
+    let err = options.join(&[vec!["a|b"]]).unwrap_err();
+    assert_eq!((err.row, err.column), (0, 0));
+
+    let grid = Grid::<i32>::from_rows(options.parse("1|2\r\n3|4").unwrap()).unwrap();
+    assert_eq!(grid.rows().collect::<Vec<_>>(), [[1, 2], [3, 4]]);
+    assert_eq!(grid.into_rows(), [[1, 2], [3, 4]]);
+}
+
+#[test]
+fn rows_need_cells() {
+    let err = Grid::<u8>::from_rows(vec![vec![], vec![], vec![]]).unwrap_err();
+    assert_eq!((err.row, err.column), (0, 0));
+    assert!(GridOptions::new("\n", ",").join::<u8>(&[vec![]]).is_err());
+    assert_eq!(Grid::<u8>::new(0, 0, 1).height(), 0);
+}
+
+#[test]
+#[should_panic(expected = "rows of a grid need at least one cell")]
+fn grids_without_columns_panic() {
+    Grid::<u8>::new(0, 3, 1);
+}
+
+#[test]
+#[should_panic(expected = "too many cells for a grid")]
+fn oversized_grids_panic() {
+    Grid::<u8>::new(usize::MAX, 2, 1);
+}
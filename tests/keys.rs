@@ -0,0 +1,43 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde_construct_classic::{from_bytes, to_bytes, ErrorKind, HashTable};
+
+#[test]
+fn integer_keys_round_trip() {
+    let levels: BTreeMap<i64, String> = [(-1, "intro"), (2, "forest"), (10, "castle")]
+        .into_iter()
+        .map(|(k, v)| (k, v.to_owned()))
+        .collect();
+    let bytes = to_bytes(&levels).unwrap();
+
+    let table: HashTable = from_bytes(&bytes).unwrap();
+    assert_eq!(table.keys().collect::<Vec<_>>(), ["-1", "2", "10"]);
+
+    assert_eq!(from_bytes::<BTreeMap<i64, String>>(&bytes).unwrap(), levels);
+}
+
+#[test]
+fn char_and_bool_keys_round_trip() {
+    let chars: HashMap<char, i32> = [('a', 1), ('é', 2)].into_iter().collect();
+    assert_eq!(from_bytes::<HashMap<char, i32>>(&to_bytes(&chars).unwrap()).unwrap(), chars);
+
+    let flags: BTreeMap<bool, i32> = [(false, 0), (true, 1)].into_iter().collect();
+    assert_eq!(from_bytes::<BTreeMap<bool, i32>>(&to_bytes(&flags).unwrap()).unwrap(), flags);
+}
+
+#[test]
+fn non_numeric_key() {
+    let mut table = HashTable::new();
+    table.insert("1", 1);
+    table.insert("boss", 2);
+    let bytes = to_bytes(&table).unwrap();
+    let err = from_bytes::<HashMap<u32, i64>>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::NonNumericKey(key) if key == "boss"));
+    assert_eq!(err.offset(), Some(28));
+
+    let mut table = HashTable::new();
+    table.insert("300", 1);
+    let bytes = to_bytes(&table).unwrap();
+    let err = from_bytes::<HashMap<u8, i64>>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::NumericOverflow));
+}
@@ -0,0 +1,112 @@
+use serde_construct_classic::{
+    from_bytes, from_bytes_with_options, table, to_bytes, DeserializerOptions, Document, ErrorKind, HashTable, Limit, Limits,
+    PayloadLength, RawValue, TableView, TypeRegistry,
+};
+use serde_derive::Deserialize;
+
+/// `hp` is at offsets 10..29, `speed` at 29..51 and `name` at 51..72.
+fn mixed() -> Vec<u8> {
+    to_bytes(&table! { "hp" => 100, "speed" => 2.5, "name" => "Bob" }).unwrap()
+}
+
+fn with_limits(limits: Limits) -> DeserializerOptions {
+    DeserializerOptions { limits, ..Default::default() }
+}
+
+/// Reads `bytes` with `limits`, returning the limit that was exceeded, its maximum and the offset.
+fn exceeded(bytes: &[u8], limits: Limits) -> (Limit, usize, usize) {
+    let err = from_bytes_with_options::<HashTable>(bytes, with_limits(limits)).unwrap_err();
+    match err.kind() {
+        ErrorKind::LimitExceeded(limit, max) => (*limit, *max, err.offset().unwrap()),
+        other => panic!("expected a limit to be exceeded, got {other}"),
+    }
+}
+
+#[test]
+fn unlimited_by_default() {
+    let bytes = mixed();
+    assert_eq!(from_bytes::<HashTable>(&bytes).unwrap().len(), 3);
+    let exact = Limits {
+        max_entries: Some(3),
+        max_key_len: Some(5),
+        max_string_len: Some(3),
+        max_depth: Some(1),
+        max_total_bytes: Some(bytes.len()),
+    };
+    assert_eq!(from_bytes_with_options::<HashTable>(&bytes, with_limits(exact)).unwrap().len(), 3);
+}
+
+#[test]
+fn each_limit_has_its_own_error() {
+    let bytes = mixed();
+    assert_eq!(exceeded(&bytes, Limits { max_entries: Some(2), ..Default::default() }), (Limit::Entries, 2, 51));
+    assert_eq!(exceeded(&bytes, Limits { max_key_len: Some(4), ..Default::default() }), (Limit::KeyLength, 4, 29));
+    assert_eq!(exceeded(&bytes, Limits { max_string_len: Some(2), ..Default::default() }), (Limit::StringLength, 2, 64));
+    assert_eq!(exceeded(&bytes, Limits { max_total_bytes: Some(71), ..Default::default() }), (Limit::TotalBytes, 71, 68));
+}
+
+#[test]
+fn limits_come_before_length_checks() {
+    // a string claiming to be far longer than the input
+    let mut bytes = mixed();
+    bytes[64..68].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(exceeded(&bytes, Limits { max_string_len: Some(100), ..Default::default() }), (Limit::StringLength, 100, 64));
+}
+
+#[test]
+fn prefixed_payloads_count_as_strings() {
+    let mut payload = 5u32.to_le_bytes().to_vec();
+    payload.extend_from_slice(b"hello");
+    let bytes = to_bytes(&table! { "blob" => RawValue { type_id: 9, payload } }).unwrap();
+    let mut type_registry = TypeRegistry::new();
+    type_registry.register(9, PayloadLength::Prefixed);
+    let options = |max| DeserializerOptions {
+        type_registry: type_registry.clone(),
+        limits: Limits { max_string_len: Some(max), ..Default::default() },
+        ..Default::default()
+    };
+    assert!(from_bytes_with_options::<HashTable>(&bytes, options(5)).is_ok());
+    let err = from_bytes_with_options::<HashTable>(&bytes, options(4)).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::StringLength, 4)));
+    assert_eq!(err.offset(), Some(23));
+}
+
+#[test]
+fn nested_tables() {
+    #[derive(Debug, Deserialize)]
+    struct Inner {
+        a: i64,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Outer {
+        inner: Inner,
+    }
+    let mut bytes = b"MAP1.0".to_vec();
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(6u32.to_le_bytes());
+    bytes.extend(b"inner\0");
+    bytes.extend(to_bytes(&table! { "a" => 7 }).unwrap());
+
+    let outer = from_bytes_with_options::<Outer>(&bytes, with_limits(Limits { max_depth: Some(2), ..Default::default() })).unwrap();
+    assert_eq!(outer.inner.a, 7);
+    let err = from_bytes_with_options::<Outer>(&bytes, with_limits(Limits { max_depth: Some(1), ..Default::default() })).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::Depth, 1)));
+    assert_eq!(err.offset(), Some(20));
+    assert_eq!(err.key(), Some("inner"));
+    let err = Document::parse_with_options(bytes, with_limits(Limits { max_depth: Some(1), ..Default::default() })).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::Depth, 1)));
+}
+
+#[test]
+fn documents_and_views() {
+    let bytes = mixed();
+    let limits = Limits { max_entries: Some(2), ..Default::default() };
+    let err = Document::parse_with_options(bytes.clone(), with_limits(limits)).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::Entries, 2)));
+    assert_eq!(err.offset(), Some(51));
+
+    let limits = Limits { max_key_len: Some(4), ..Default::default() };
+    let err = TableView::with_options(&bytes, with_limits(limits)).err().unwrap();
+    assert!(matches!(err.kind(), ErrorKind::LimitExceeded(Limit::KeyLength, 4)));
+    assert_eq!(err.to_string(), "At offset 29: Key longer than 4 bytes");
+}
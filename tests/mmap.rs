@@ -0,0 +1,55 @@
+#![cfg(feature = "mmap")]
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use serde_construct_classic::{from_path, to_bytes, ErrorKind, HashTable, TableView, Value};
+
+/// A file in the temp directory, removed when dropped, even if the test fails.
+struct TempFile(PathBuf);
+
+impl Deref for TempFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn temp_file(name: &str, bytes: &[u8]) -> TempFile {
+    let file = TempFile(std::env::temp_dir().join(format!("cstc-{}-{name}", std::process::id())));
+    std::fs::write(&file.0, bytes).unwrap();
+    file
+}
+
+#[test]
+fn load_mapped_file() {
+    let mut table = HashTable::new();
+    table.push("level", 3);
+    table.push("name", "Caves");
+    let path = temp_file("level.lvl", &to_bytes(&table).unwrap());
+
+    assert_eq!(from_path::<HashTable, _>(&*path).unwrap(), table);
+    let view = TableView::open(&*path).unwrap();
+    assert_eq!(view.get("name"), Some(Value::String("Caves".to_owned())));
+    assert_eq!(view.get_as::<&str>("name").unwrap(), Some("Caves"));
+    drop(view);
+
+    let mut bytes = to_bytes(&table).unwrap();
+    bytes.truncate(bytes.len() - 2);
+    let path = temp_file("truncated.lvl", &bytes);
+    let err = from_path::<HashTable, _>(&*path).unwrap_err();
+    assert_eq!(err.offset(), Some(bytes.len() - 8));
+}
+
+#[test]
+fn missing_file() {
+    let err = from_path::<HashTable, _>("/nonexistent/table.lvl").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Io(_)));
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use serde_construct_classic::testing::{compare_tables, TableComparison};
+use serde_construct_classic::{
+    from_bytes, from_bytes_with_options, from_value, table, to_bytes, to_bytes_with_options, to_value, DeserializerOptions,
+    Document, ErrorKind, HashTable, KeyOrder, PayloadLength, RawValue, SerializerOptions, TableView, TypeRegistry, Value,
+};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Inner {
+    x: i64,
+    label: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Outer {
+    name: String,
+    inner: Inner,
+    speed: f64,
+}
+
+fn outer() -> Outer {
+    Outer { name: "Bob".into(), inner: Inner { x: 7, label: "door".into() }, speed: 2.5 }
+}
+
+fn nested() -> SerializerOptions {
+    SerializerOptions { nested_tables: true, ..Default::default() }
+}
+
+fn inner_table() -> HashTable {
+    table! { "x" => 7, "label" => "door" }
+}
+
+#[test]
+fn nested_tables_are_opt_in() {
+    assert!(matches!(to_bytes(&outer()), Err(ErrorKind::UnsupportedValue)));
+    let mut levels: BTreeMap<String, BTreeMap<String, i64>> = BTreeMap::new();
+    levels.entry("one".into()).or_default();
+    assert!(matches!(to_bytes(&levels), Err(ErrorKind::UnsupportedValue)));
+    assert!(matches!(to_bytes(&table! { "inner" => inner_table() }), Err(ErrorKind::UnsupportedValue)));
+}
+
+#[test]
+fn structs_round_trip_as_tables_in_place_of_values() {
+    let bytes = to_bytes_with_options(&outer(), nested()).unwrap();
+    assert_eq!(from_bytes::<Outer>(&bytes).unwrap(), outer());
+
+    // the inner table goes right after its key, with a header but no type tag
+    let inner = to_bytes(&Inner { x: 7, label: "door".into() }).unwrap();
+    let start = bytes.windows(6).position(|w| w == b"inner\0").unwrap() + 6;
+    assert_eq!(&bytes[start..start + inner.len()], inner);
+}
+
+#[test]
+fn maps_round_trip() {
+    let mut levels: BTreeMap<String, BTreeMap<String, i64>> = BTreeMap::new();
+    levels.entry("one".into()).or_default().insert("hp".into(), 10);
+    levels.entry("two".into()).or_default();
+    let bytes = to_bytes_with_options(&levels, nested()).unwrap();
+    assert_eq!(from_bytes::<BTreeMap<String, BTreeMap<String, i64>>>(&bytes).unwrap(), levels);
+
+    let json: serde_json::Value = from_bytes(&to_bytes_with_options(&outer(), nested()).unwrap()).unwrap();
+    assert_eq!(json, json!({ "name": "Bob", "inner": { "x": 7, "label": "door" }, "speed": 2.5 }));
+    assert_eq!(from_bytes::<Outer>(&to_bytes_with_options(&json, nested()).unwrap()).unwrap(), outer());
+}
+
+#[test]
+fn nested_entries_are_sorted_too() {
+    let options = SerializerOptions { sort_keys: Some(KeyOrder::Unicode), nested_tables: true };
+    let bytes = to_bytes_with_options(&outer(), options.clone()).unwrap();
+    let json: serde_json::Value = from_bytes(&bytes).unwrap();
+    assert_eq!(to_bytes_with_options(&json, nested()).unwrap(), bytes);
+}
+
+#[test]
+fn dynamic_values_hold_nested_tables() {
+    let bytes = to_bytes_with_options(&outer(), nested()).unwrap();
+    let table: HashTable = from_bytes(&bytes).unwrap();
+    assert_eq!(table.get("inner"), Some(&Value::Table(inner_table())));
+    assert_eq!(to_bytes_with_options(&table, nested()).unwrap(), bytes);
+    assert_eq!(from_bytes::<Value>(&bytes).unwrap(), Value::Table(table));
+}
+
+#[test]
+fn raw_values_and_tables_are_told_apart() {
+    let raw = RawValue { type_id: 9, payload: vec![1, 2, 3] };
+    // a table whose first key is `$type` but that isn't a raw value
+    let odd = table! { "$type" => "x", "a" => 1 };
+    let table = table! { "raw" => raw.clone(), "odd" => odd.clone() };
+    let bytes = to_bytes_with_options(&table, nested()).unwrap();
+    let mut type_registry = TypeRegistry::new();
+    type_registry.register(9, PayloadLength::Fixed(3));
+    let options = DeserializerOptions { type_registry, ..Default::default() };
+    let read: HashTable = from_bytes_with_options(&bytes, options.clone()).unwrap();
+    assert_eq!(read.get("raw"), Some(&Value::Raw(raw)));
+    assert_eq!(read.get("odd"), Some(&Value::Table(odd.clone())));
+    assert_eq!(to_bytes_with_options(&read, nested()).unwrap(), bytes);
+
+    // JSON objects don't keep their order, so compare what's read back instead
+    let json: serde_json::Value = from_bytes_with_options(&bytes, options.clone()).unwrap();
+    assert_eq!(json["odd"], json!({ "$type": "x", "a": 1 }));
+    let again = to_bytes_with_options(&json, nested()).unwrap();
+    let again: HashTable = from_bytes_with_options(&again, options).unwrap();
+    assert_eq!(again.get("raw"), read.get("raw"));
+    assert_eq!(again.get("odd"), read.get("odd"));
+    assert!(matches!(to_bytes(&table! { "odd" => odd }), Err(ErrorKind::UnsupportedValue)));
+}
+
+#[test]
+fn views_and_documents_read_nested_tables() {
+    let bytes = to_bytes_with_options(&outer(), nested()).unwrap();
+    let view = TableView::new(&bytes).unwrap();
+    assert_eq!(view.keys().collect::<Vec<_>>(), ["name", "inner", "speed"]);
+    assert_eq!(view.get("inner"), Some(Value::Table(inner_table())));
+    assert_eq!(view.get_as::<Inner>("inner").unwrap(), Some(Inner { x: 7, label: "door".into() }));
+    assert_eq!(view.get("speed"), Some(Value::Float(2.5)));
+
+    let mut document = Document::parse(bytes.clone()).unwrap();
+    assert_eq!(document.get("inner"), Some(Value::Table(inner_table())));
+    assert_eq!(document.to_bytes(), bytes);
+    // documents only write what Construct can read
+    assert!(matches!(document.set("other", inner_table()), Err(ErrorKind::UnsupportedValue)));
+}
+
+#[test]
+fn nested_tables_are_compared_entry_by_entry() {
+    let bytes = to_bytes_with_options(&outer(), nested()).unwrap();
+    assert!(compare_tables(&bytes, &bytes, &TableComparison::default()).is_ok());
+
+    let mut moved = outer();
+    moved.inner.x = 8;
+    let moved = to_bytes_with_options(&moved, nested()).unwrap();
+    let diff = compare_tables(&bytes, &moved, &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, ["\"inner\": left table of 2 entries at offset 41, right table of 2 entries at offset 41"]);
+}
+
+#[test]
+fn values_convert_to_nested_tables() {
+    let table = to_value(&outer()).unwrap();
+    assert_eq!(table.get("inner"), Some(&Value::Table(inner_table())));
+    assert_eq!(from_value::<Outer>(table).unwrap(), outer());
+}
+
+#[test]
+fn a_value_where_a_table_belongs() {
+    let bytes = to_bytes(&json!({ "name": "Bob", "inner": 7, "speed": 2.5 })).unwrap();
+    let err = from_bytes::<Outer>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+    assert_eq!(err.key(), Some("inner"));
+}
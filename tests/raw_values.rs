@@ -0,0 +1,74 @@
+use serde_construct_classic::{
+    from_bytes, from_bytes_with_options, to_bytes, DeserializerOptions, ErrorKind, HashTable, PayloadLength,
+    RawValue, TypeRegistry, Value, TYPE_I64, TYPE_STRING,
+};
+use serde_json::json;
+
+fn key(out: &mut Vec<u8>, key: &str) {
+    out.extend((key.len() as u32 + 1).to_le_bytes());
+    out.extend(key.as_bytes());
+    out.push(0);
+}
+
+/// A table with a value of type 7 between two ordinary entries.
+fn table_with_unknown_type() -> Vec<u8> {
+    let mut out = b"MAP1.0".to_vec();
+    out.extend(3u32.to_le_bytes());
+    key(&mut out, "a");
+    out.extend(TYPE_I64.to_le_bytes());
+    out.extend(5i64.to_le_bytes());
+    key(&mut out, "b");
+    out.extend(7u32.to_le_bytes());
+    out.extend([1, 2, 3, 4, 5, 6]);
+    key(&mut out, "c");
+    out.extend(TYPE_STRING.to_le_bytes());
+    key(&mut out, "hi");
+    out
+}
+
+fn lenient() -> DeserializerOptions {
+    DeserializerOptions {
+        type_registry: TypeRegistry::with_fallback(PayloadLength::UntilNextKey),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn unknown_type_fails_without_registry() {
+    let bytes = table_with_unknown_type();
+    let err = from_bytes::<HashTable>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::UnknownTypeId(7)));
+    assert_eq!(err.offset(), Some(34));
+}
+
+#[test]
+fn raw_value_round_trips() {
+    let bytes = table_with_unknown_type();
+    let table: HashTable = from_bytes_with_options(&bytes, lenient()).unwrap();
+    assert_eq!(table.get("b"), Some(&Value::Raw(RawValue { type_id: 7, payload: vec![1, 2, 3, 4, 5, 6] })));
+    assert_eq!(table.get("c"), Some(&Value::String("hi".to_owned())));
+    assert_eq!(to_bytes(&table).unwrap(), bytes);
+}
+
+#[test]
+fn raw_value_round_trips_through_json() {
+    let bytes = table_with_unknown_type();
+    let json: serde_json::Value = from_bytes_with_options(&bytes, lenient()).unwrap();
+    assert_eq!(json, json!({ "a": 5, "b": { "$type": 7, "$raw": "AQIDBAUG" }, "c": "hi" }));
+    assert_eq!(to_bytes(&json).unwrap(), bytes);
+}
+
+#[test]
+fn registered_length() {
+    let bytes = table_with_unknown_type();
+    let mut type_registry = TypeRegistry::new();
+    type_registry.register(7, PayloadLength::Fixed(6));
+    let options = DeserializerOptions { type_registry, ..Default::default() };
+    let table: HashTable = from_bytes_with_options(&bytes, options).unwrap();
+    assert_eq!(table.len(), 3);
+
+    let mut type_registry = TypeRegistry::new();
+    type_registry.register(7, PayloadLength::Fixed(4));
+    let options = DeserializerOptions { type_registry, ..Default::default() };
+    assert!(from_bytes_with_options::<HashTable>(&bytes, options).is_err());
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use serde_construct_classic::{from_bytes, to_bytes_with_options, HashTable, KeyOrder, SerializerOptions};
+
+fn sorted_keys(keys: &[&str], order: KeyOrder) -> Vec<String> {
+    let map: HashMap<&str, i32> = keys.iter().map(|k| (*k, 0)).collect();
+    let options = SerializerOptions { sort_keys: Some(order), ..Default::default() };
+    let bytes = to_bytes_with_options(&map, options).unwrap();
+    let table: HashTable = from_bytes(&bytes).unwrap();
+    table.keys().map(str::to_owned).collect()
+}
+
+#[test]
+fn orders() {
+    assert_eq!(sorted_keys(&["é", "€", "b", "B"], KeyOrder::Unicode), ["B", "b", "é", "€"]);
+    assert_eq!(sorted_keys(&["é", "€", "b", "B"], KeyOrder::Windows1252), ["B", "b", "€", "é"]);
+    assert_eq!(
+        sorted_keys(&["a10", "a2", "a02", "b1", "a", "a9b"], KeyOrder::Natural),
+        ["a", "a2", "a02", "a9b", "a10", "b1"],
+    );
+}
+
+#[test]
+fn hash_map_bytes_are_stable() {
+    let map: HashMap<String, i32> = (0..50).map(|i| (format!("key{i}"), i)).collect();
+    let mut entries: Vec<_> = map.clone().into_iter().collect();
+    entries.reverse();
+    let shuffled: HashMap<String, i32> = entries.into_iter().collect();
+    let options = SerializerOptions { sort_keys: Some(KeyOrder::Natural), ..Default::default() };
+    assert_eq!(
+        to_bytes_with_options(&map, options.clone()).unwrap(),
+        to_bytes_with_options(&shuffled, options).unwrap(),
+    );
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use serde_construct_classic::{
+    from_bytes, from_bytes_with_options, from_value, table, to_bytes, to_bytes_with_options, DeserializerOptions, ErrorKind,
+    SerializerOptions, Spanned, TableView, Value,
+};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Level {
+    hp: i64,
+    speed: Spanned<f64>,
+    name: Spanned<String>,
+}
+
+fn mixed() -> Vec<u8> {
+    include_bytes!("fixtures/valid/mixed.bin").to_vec()
+}
+
+#[test]
+fn spans_of_fields() {
+    let bytes = mixed();
+    let level: Level = from_bytes(&bytes).unwrap();
+    assert_eq!(level.hp, 100);
+
+    assert_eq!(*level.speed.get_ref(), 2.5);
+    assert_eq!(level.speed.key_span(), 29..39);
+    assert_eq!(level.speed.value_span(), 39..51);
+    assert_eq!(level.speed.tag_offset(), 39);
+    assert_eq!(&bytes[level.speed.tag_offset() + 4..level.speed.value_span().end], 2.5f64.to_le_bytes());
+
+    assert_eq!(level.name.get_ref(), "Bob");
+    assert_eq!(&bytes[level.name.key_span()], b"\x05\0\0\0name\0");
+    assert_eq!(level.name.value_span(), 51 + 9..bytes.len());
+}
+
+#[test]
+fn serializes_as_the_value() {
+    let bytes = mixed();
+    let level: Level = from_bytes(&bytes).unwrap();
+    assert_eq!(to_bytes(&level).unwrap(), bytes);
+}
+
+#[test]
+fn spanned_values_in_maps() {
+    let bytes = to_bytes(&table! { "a" => 1, "b" => "two" }).unwrap();
+    let map: BTreeMap<String, Spanned<Value>> = from_bytes(&bytes).unwrap();
+    assert_eq!(map["a"].get_ref(), &Value::Int(1));
+    assert_eq!(map["a"].key_span(), 10..16);
+    assert_eq!(map["b"].key_span(), 28..34);
+    assert_eq!(map["b"].value_span(), 34..bytes.len());
+}
+
+#[test]
+fn errors_inside_spanned_values() {
+    let bytes = to_bytes(&table! { "hp" => 1, "speed" => 2, "name" => "x" }).unwrap();
+    let err = from_bytes::<Level>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+    assert_eq!(err.key(), Some("speed"));
+}
+
+#[test]
+fn other_deserializers_are_rejected() {
+    assert!(from_value::<BTreeMap<String, Spanned<i64>>>(table! { "a" => 1 }).is_err());
+}
+
+#[test]
+fn spans_from_views() {
+    let bytes = mixed();
+    let view = TableView::new(&bytes).unwrap();
+    let speed = view.get_as::<Spanned<f64>>("speed").unwrap().unwrap();
+    assert_eq!(*speed.get_ref(), 2.5);
+    assert_eq!(speed.key_span(), 29..39);
+    assert_eq!(speed.value_span(), 39..51);
+}
+
+#[test]
+fn spans_of_nested_tables() {
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Inner {
+        a: i64,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Outer {
+        inner: Spanned<Inner>,
+        after: Spanned<String>,
+    }
+    #[derive(Serialize)]
+    struct Plain {
+        inner: Inner,
+        after: String,
+    }
+    let nested = SerializerOptions { nested_tables: true, ..Default::default() };
+    let bytes = to_bytes_with_options(&Plain { inner: Inner { a: 7 }, after: "x".into() }, nested).unwrap();
+    let outer: Outer = from_bytes(&bytes).unwrap();
+    assert_eq!(outer.inner.get_ref(), &Inner { a: 7 });
+    assert_eq!(outer.inner.key_span(), 10..20);
+    assert_eq!(&bytes[outer.inner.value_span()], to_bytes(&Inner { a: 7 }).unwrap());
+    assert_eq!(outer.after.key_span().start, outer.inner.value_span().end);
+}
+
+#[test]
+fn spans_of_construct_defaults() {
+    let bytes = to_bytes(&table! { "hp" => 1 }).unwrap();
+    let options = DeserializerOptions { construct_defaults: true, ..Default::default() };
+    let level: Level = from_bytes_with_options(&bytes, options).unwrap();
+    assert_eq!(*level.speed.get_ref(), 0.0);
+    assert_eq!(level.speed.key_span(), 0..0);
+    assert_eq!(level.speed.value_span(), 0..0);
+    assert_eq!(level.name.get_ref(), "");
+}
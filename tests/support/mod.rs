@@ -0,0 +1,78 @@
+//! Helpers shared by the integration tests.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Describes how `actual` differs from `expected`, or returns `None` if they're identical.
+///
+/// The report gives both lengths, the number of differing bytes, the entry the first difference falls in,
+/// and a hex dump of both sides around it.
+pub fn byte_diff_report(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let first = match expected.iter().zip(actual).position(|(a, b)| a != b) {
+        Some(offset) => offset,
+        None if expected.len() == actual.len() => return None,
+        None => expected.len().min(actual.len()),
+    };
+    let differing = expected.iter().zip(actual).filter(|(a, b)| a != b).count() + expected.len().abs_diff(actual.len());
+    let mut report = String::new();
+    writeln!(report, "expected {} bytes, got {}; {differing} bytes differ", expected.len(), actual.len()).unwrap();
+    writeln!(report, "first difference at offset {first} (0x{first:x}), in {}", describe_offset(expected, first)).unwrap();
+    let start = first / 16 * 16;
+    let rows = start.saturating_sub(16)..start + 32;
+    writeln!(report, "expected:").unwrap();
+    hex_dump(&mut report, expected, rows.clone(), actual);
+    writeln!(report, "actual:").unwrap();
+    hex_dump(&mut report, actual, rows, expected);
+    Some(report)
+}
+
+/// Writes rows of 16 bytes within `range`, bracketing the bytes that differ from `other`.
+fn hex_dump(report: &mut String, bytes: &[u8], range: Range<usize>, other: &[u8]) {
+    for row in range.step_by(16).take_while(|&row| row < bytes.len()) {
+        write!(report, "  {row:08x} ").unwrap();
+        for (offset, &byte) in bytes.iter().enumerate().skip(row).take(16) {
+            if other.get(offset) == Some(&byte) {
+                write!(report, " {byte:02x} ").unwrap();
+            } else {
+                write!(report, "[{byte:02x}]").unwrap();
+            }
+        }
+        writeln!(report).unwrap();
+    }
+}
+
+/// Names the part of a table that `offset` falls in, by walking its entries.
+/// Values with unknown type tags stop the walk, since their length can't be known.
+fn describe_offset(table: &[u8], offset: usize) -> String {
+    if offset < 6 {
+        return "the header".into();
+    }
+    if offset < 10 {
+        return "the key count".into();
+    }
+    let read_u32 = |at: usize| table.get(at..at + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize);
+    let mut at = 10;
+    let mut index = 0;
+    while at < table.len() {
+        let Some(key_len) = read_u32(at) else { break };
+        let key = String::from_utf8_lossy(table.get(at + 4..at + 4 + key_len.saturating_sub(1)).unwrap_or_default()).into_owned();
+        let value_start = at + 4 + key_len;
+        if offset < value_start {
+            return format!("the key of entry {index} ({key:?})");
+        }
+        let value_len = match read_u32(value_start) {
+            Some(0 | 1) => 12,
+            Some(2) => match read_u32(value_start + 4) {
+                Some(len) => 8 + len,
+                None => break,
+            },
+            _ => break,
+        };
+        if offset < value_start + value_len {
+            return format!("the value of entry {index} ({key:?})");
+        }
+        at = value_start + value_len;
+        index += 1;
+    }
+    "the end of the table".into()
+}
@@ -0,0 +1,84 @@
+use serde_construct_classic::{table, to_bytes, HashTable, Value};
+
+#[test]
+fn tags_follow_the_literals() {
+    let table = table! { "hp" => 100, "speed" => 2.5, "name" => "Bob", "alive" => true };
+    let entries: Vec<_> = table.iter().map(|(k, v)| (k, v.clone())).collect();
+    assert_eq!(entries, [
+        ("hp", Value::Int(100)),
+        ("speed", Value::Float(2.5)),
+        ("name", Value::from("Bob")),
+        ("alive", Value::Int(1)),
+    ]);
+}
+
+#[test]
+fn overrides() {
+    let level = 2;
+    let small: u8 = 7;
+    let table = table! {
+        "x" => float 3,
+        "y" => int small,
+        "level" => string level,
+        "ratio" => float 0.5f32,
+        "far" => float 3_000_000_000,
+        "gold" => int 3_000_000_000,
+    };
+    assert_eq!(table.get("x"), Some(&Value::Float(3.0)));
+    assert_eq!(table.get("y"), Some(&Value::Int(7)));
+    assert_eq!(table.get("level"), Some(&Value::from("2")));
+    assert_eq!(table.get("ratio"), Some(&Value::Float(0.5)));
+    assert_eq!(table.get("far"), Some(&Value::Float(3e9)));
+    assert_eq!(table.get("gold"), Some(&Value::Int(3_000_000_000)));
+}
+
+#[test]
+fn integer_literals_are_i64() {
+    let name = "Bob";
+    let table = table! { "gold" => 3_000_000_000, "debt" => -3_000_000_000, "name" => name };
+    assert_eq!(table.get("gold"), Some(&Value::Int(3_000_000_000)));
+    assert_eq!(table.get("debt"), Some(&Value::Int(-3_000_000_000)));
+    let table = table! { "name" => name, "gold" => 3_000_000_000, "ratio" => 0.5f32 };
+    assert_eq!(table.get("gold"), Some(&Value::Int(3_000_000_000)));
+}
+
+#[test]
+fn large_tables() {
+    // more entries than the default `recursion_limit`
+    let table = table! {
+        "0" => 0, "1" => 1, "2" => 2, "3" => 3, "4" => 4, "5" => 5, "6" => 6, "7" => 7, "8" => 8, "9" => 9,
+        "10" => 10, "11" => 11, "12" => 12, "13" => 13, "14" => 14, "15" => 15, "16" => 16, "17" => 17, "18" => 18, "19" => 19,
+        "20" => 20, "21" => 21, "22" => 22, "23" => 23, "24" => 24, "25" => 25, "26" => 26, "27" => 27, "28" => 28, "29" => 29,
+        "30" => 30, "31" => 31, "32" => 32, "33" => 33, "34" => 34, "35" => 35, "36" => 36, "37" => 37, "38" => 38, "39" => 39,
+        "40" => 40, "41" => 41, "42" => 42, "43" => 43, "44" => 44, "45" => 45, "46" => 46, "47" => 47, "48" => 48, "49" => 49,
+        "50" => 50, "51" => 51, "52" => 52, "53" => 53, "54" => 54, "55" => 55, "56" => 56, "57" => 57, "58" => 58, "59" => 59,
+        "60" => 60, "61" => 61, "62" => 62, "63" => 63, "64" => 64, "65" => 65, "66" => 66, "67" => 67, "68" => 68, "69" => 69,
+        "70" => 70, "71" => 71, "72" => 72, "73" => 73, "74" => 74, "75" => 75, "76" => 76, "77" => 77, "78" => 78, "79" => 79,
+        "80" => 80, "81" => 81, "82" => 82, "83" => 83, "84" => 84, "85" => 85, "86" => 86, "87" => 87, "88" => 88, "89" => 89,
+        "90" => 90, "91" => 91, "92" => 92, "93" => 93, "94" => 94, "95" => 95, "96" => 96, "97" => 97, "98" => 98, "99" => 99,
+        "100" => 100, "101" => 101, "102" => 102, "103" => 103, "104" => 104, "105" => 105, "106" => 106, "107" => 107, "108" => 108, "109" => 109,
+        "110" => 110, "111" => 111, "112" => 112, "113" => 113, "114" => 114, "115" => 115, "116" => 116, "117" => 117, "118" => 118, "119" => 119,
+        "120" => 120, "121" => 121, "122" => 122, "123" => 123, "124" => 124, "125" => 125, "126" => 126, "127" => 127, "128" => 128, "129" => 129,
+        "130" => 130, "131" => 131, "132" => 132, "133" => 133, "134" => 134, "135" => 135, "136" => 136, "137" => 137, "138" => 138, "139" => 139,
+        "140" => 140, "141" => 141, "142" => 142, "143" => 143, "144" => 144, "145" => 145, "146" => 146, "147" => 147, "148" => 148, "149" => 149,
+    };
+    assert_eq!(table.len(), 150);
+    assert_eq!(table.get("149"), Some(&Value::Int(149)));
+}
+
+#[test]
+fn expressions_and_duplicates() {
+    let name = String::from("Alice");
+    let key = "score";
+    let table = table! { "name" => name.clone(), key => 10 * 3, "a" => 1, "a" => 2 };
+    assert_eq!(table.get("name"), Some(&Value::String(name)));
+    assert_eq!(table.get("score"), Some(&Value::Int(30)));
+    assert_eq!(table.iter().filter(|(k, _)| *k == "a").count(), 2);
+    assert_eq!(table! {}, HashTable::new());
+}
+
+#[test]
+fn writes_the_same_bytes_as_construct() {
+    let table = table! { "hp" => 100, "speed" => 2.5, "name" => "Bob" };
+    assert_eq!(to_bytes(&table).unwrap(), include_bytes!("fixtures/valid/mixed.bin"));
+}
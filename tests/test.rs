@@ -1,12 +1,18 @@
+mod support;
+
 use serde_construct_classic::from_bytes;
 use serde_construct_classic::to_bytes;
 use serde_json::Value;
-use std::cmp::min;
-use std::{env, fs};
+use std::env;
 
+/// Round-trips every file in `SAMPLES_DIR` through `serde_json::Value`, for checking against real game files.
+/// Without `SAMPLES_DIR` there's nothing to check; `conformance.rs` covers the committed fixtures.
 #[test]
 fn test() {
-    let dir_path = env::var("SAMPLES_DIR").unwrap();
+    let Ok(dir_path) = env::var("SAMPLES_DIR") else {
+        eprintln!("SAMPLES_DIR is not set, skipping");
+        return;
+    };
     for entry in std::fs::read_dir(dir_path).unwrap() {
         let path = entry.unwrap().path();
         if !path.is_file() { continue; }
@@ -15,22 +21,15 @@ fn test() {
         eprintln!("Deserializing {name}");
         let value = match from_bytes::<Value>(&bytes) {
             Ok(value) => value,
-            Err(err) => panic!("{}", err),
+            Err(err) => panic!("{name}: {err}"),
         };
         eprintln!("Reserializing");
         let bytes_out = match to_bytes(&value) {
             Ok(bytes) => bytes,
-            Err(err) => panic!("{}", err),
+            Err(err) => panic!("{name}: {err}"),
         };
-        for i in 0..min(bytes.len(), bytes_out.len()) {
-            if bytes[i] != bytes_out[i] {
-                fs::write(format!("{}.0", name), &bytes).unwrap();
-                fs::write(format!("{}.1", name), &bytes_out).unwrap();
-                panic!("Difference at offset {i}: 0x{:02x} != 0x{:02x}", bytes[i], bytes_out[i])
-            }
-        }
-        if bytes.len() != bytes_out.len() {
-            panic!("Input is length {}, output is length {}", bytes.len(), bytes_out.len());
+        if let Some(report) = support::byte_diff_report(&bytes, &bytes_out) {
+            panic!("{name} changed in a round trip:\n{report}");
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,86 @@
+use serde_construct_classic::testing::{compare_tables, TableComparison};
+use serde_construct_classic::{assert_table_eq, assert_table_matches, to_bytes, HashTable, Value};
+
+fn table(entries: &[(&str, Value)]) -> Vec<u8> {
+    let table: HashTable = entries.iter().map(|(key, value)| (key.to_string(), value.clone())).collect();
+    to_bytes(&table).unwrap()
+}
+
+fn save() -> Vec<u8> {
+    table(&[("hp", Value::Int(100)), ("speed", Value::Float(2.5)), ("name", Value::from("Bob"))])
+}
+
+#[test]
+fn identical_tables_are_equal() {
+    assert_table_eq!(save(), save());
+    assert_table_eq!(save(), &save()[..]);
+}
+
+#[test]
+fn diffs_list_each_key_with_types_and_offsets() {
+    let other = table(&[("hp", Value::Float(100.0)), ("speed", Value::Float(2.5)), ("mp", Value::Int(5))]);
+    let diff = compare_tables(&save(), &other, &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, [
+        "\"hp\": left int 100 at offset 17, right float 100.0 at offset 17",
+        "\"name\": only in left, string \"Bob\" at offset 60",
+        "\"mp\": only in right, int 5 at offset 58",
+    ]);
+}
+
+#[test]
+#[should_panic(expected = "tables differ at 1 key(s):\n  \"speed\": left float 2.5 at offset 39, right float 2.75 at offset 39")]
+fn failures_panic_with_the_diff() {
+    let other = table(&[("hp", Value::Int(100)), ("speed", Value::Float(2.75)), ("name", Value::from("Bob"))]);
+    assert_table_eq!(save(), other);
+}
+
+#[test]
+fn order_matters_unless_ignored() {
+    let reordered = table(&[("name", Value::from("Bob")), ("hp", Value::Int(100)), ("speed", Value::Float(2.5))]);
+    let diff = compare_tables(&save(), &reordered, &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, ["\"name\": after \"speed\" in left, before it in right"]);
+    assert_table_matches!(save(), reordered, ignore_order);
+}
+
+#[test]
+fn an_inserted_entry_is_the_only_difference() {
+    let inserted = table(&[("mp", Value::Int(5)), ("hp", Value::Int(100)), ("speed", Value::Float(2.5)), ("name", Value::from("Bob"))]);
+    let diff = compare_tables(&save(), &inserted, &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, ["\"mp\": only in right, int 5 at offset 17"]);
+}
+
+#[test]
+fn float_tolerance() {
+    let close = table(&[("hp", Value::Int(100)), ("speed", Value::Float(2.5 + 1e-12)), ("name", Value::from("Bob"))]);
+    assert!(compare_tables(&save(), &close, &TableComparison::default()).is_err());
+    assert_table_matches!(save(), close, float_tolerance = 1e-9);
+
+    let infinite = table(&[("x", Value::Float(f64::INFINITY)), ("y", Value::Float(f64::NEG_INFINITY))]);
+    assert_table_matches!(infinite, infinite, float_tolerance = 1e-9);
+
+    let nan = table(&[("x", Value::Float(f64::NAN))]);
+    assert_table_eq!(nan, nan);
+    let zeros = compare_tables(&table(&[("x", Value::Float(0.0))]), &table(&[("x", Value::Float(-0.0))]), &TableComparison::default());
+    assert!(zeros.is_err());
+}
+
+#[test]
+fn ignored_keys() {
+    let later = table(&[("hp", Value::Int(100)), ("saved_at", Value::Int(1_700_000_000)), ("speed", Value::Float(2.5)), ("name", Value::from("Bob"))]);
+    assert_table_matches!(save(), later, ignore_keys = ["saved_at"]);
+    assert_table_matches!(later, save(), ignore_order, ignore_keys = ["saved_at", "name"], float_tolerance = 0.1,);
+}
+
+#[test]
+fn duplicate_keys_are_paired_in_order() {
+    let left = table(&[("a", Value::Int(1)), ("a", Value::Int(2))]);
+    let right = table(&[("a", Value::Int(1)), ("a", Value::Int(3))]);
+    let diff = compare_tables(&left, &right, &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, ["\"a\": left int 2 at offset 34, right int 3 at offset 34"]);
+}
+
+#[test]
+fn undecodable_tables() {
+    let diff = compare_tables(b"MAP1.0", &save(), &TableComparison::default()).unwrap_err();
+    assert_eq!(diff.lines, ["left table can't be decoded: At offset 6: Unexpected end of input"]);
+}
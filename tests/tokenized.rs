@@ -0,0 +1,73 @@
+use serde_construct_classic::{from_bytes, to_bytes, with, Escape, HashTable, TokenFormat, TokenOptions, Tokenized, Value};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Inventory {
+    items: Tokenized<String>,
+    #[serde(with = "with::tokenized")]
+    counts: Vec<u32>,
+}
+
+fn table(entries: &[(&str, &str)]) -> Vec<u8> {
+    let table: HashTable = entries.iter().map(|(key, value)| (key.to_string(), Value::from(*value))).collect();
+    to_bytes(&table).unwrap()
+}
+
+#[test]
+fn lists_round_trip_through_strings() {
+    let inventory = Inventory {
+        items: vec!["sword".to_string(), "shield".to_string(), "potion".to_string()].into(),
+        counts: vec![1, 1, 5],
+    };
+    let bytes = to_bytes(&inventory).unwrap();
+    assert_eq!(bytes, table(&[("items", "sword,shield,potion"), ("counts", "1,1,5")]));
+    assert_eq!(from_bytes::<Inventory>(&bytes).unwrap(), inventory);
+}
+
+#[test]
+fn empty_strings_are_empty_lists() {
+    let inventory: Inventory = from_bytes(&table(&[("items", ""), ("counts", "")])).unwrap();
+    assert!(inventory.items.is_empty() && inventory.counts.is_empty());
+}
+
+#[test]
+fn parse_errors_name_the_token() {
+    let err = from_bytes::<Inventory>(&table(&[("items", "a"), ("counts", "1,2,x,4")])).unwrap_err();
+    assert!(err.to_string().contains("Token 2: \"x\" could not be parsed"), "{err}");
+}
+
+#[test]
+fn separators_in_tokens_are_rejected_without_escaping() {
+    let inventory = Inventory { items: vec!["a,b".to_string()].into(), counts: Vec::new() };
+    let err = to_bytes(&inventory).unwrap_err();
+    assert!(err.to_string().contains("Token 0"), "{err}");
+}
+
+struct Pipes;
+
+impl TokenFormat for Pipes {
+    const OPTIONS: TokenOptions = TokenOptions { trim: true, escape: Escape::Backslash, ..TokenOptions::new('|') };
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Dialogue {
+    lines: Tokenized<String, Pipes>,
+}
+
+#[test]
+fn custom_formats() {
+    let dialogue: Dialogue = from_bytes(&table(&[("lines", " Hi | Pipe: \\| | Slash: \\\\ ")])).unwrap();
+    assert_eq!(*dialogue.lines, ["Hi", "Pipe: |", "Slash: \\"]);
+    let bytes = to_bytes(&dialogue).unwrap();
+    assert_eq!(bytes, table(&[("lines", "Hi|Pipe: \\||Slash: \\\\")]));
+    assert_eq!(from_bytes::<Dialogue>(&bytes).unwrap(), dialogue);
+}
+
+#[test]
+fn options_split_and_join() {
+    let options = TokenOptions::new(';');
+    assert_eq!(options.split("a;;b"), ["a", "", "b"]);
+    assert_eq!(options.join([1, 2, 3]).unwrap(), "1;2;3");
+    assert_eq!(options.parse::<i64>("4;-5").unwrap(), [4, -5]);
+    assert_eq!(options.parse::<i64>("4;five").unwrap_err().index, 1);
+}
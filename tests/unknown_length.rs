@@ -0,0 +1,61 @@
+use std::io::{Cursor, Seek, SeekFrom, Write};
+
+use serde::{Serialize, Serializer};
+use serde_construct_classic::{from_bytes, to_bytes, to_writer, HashTable, Value};
+use serde_derive::Serialize;
+
+#[derive(Serialize)]
+struct Position {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize)]
+struct Player {
+    name: String,
+    #[serde(flatten)]
+    position: Position,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+}
+
+struct Squares(u32);
+
+impl Serialize for Squares {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map((1..=self.0).filter(|n| n % 2 == 1).map(|n| (format!("sq{n}"), n * n)))
+    }
+}
+
+fn key_count(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[6..10].try_into().unwrap())
+}
+
+#[test]
+fn flattened_struct() {
+    let player = Player { name: "Bob".to_owned(), position: Position { x: 3, y: -4 }, title: None };
+    let bytes = to_bytes(&player).unwrap();
+    assert_eq!(key_count(&bytes), 3);
+    let table: HashTable = from_bytes(&bytes).unwrap();
+    assert_eq!(table.keys().collect::<Vec<_>>(), ["name", "x", "y"]);
+    assert_eq!(table.get("y"), Some(&Value::Int(-4)));
+}
+
+#[test]
+fn collected_iterator() {
+    let bytes = to_bytes(&Squares(6)).unwrap();
+    assert_eq!(key_count(&bytes), 3);
+    let table: HashTable = from_bytes(&bytes).unwrap();
+    assert_eq!(table.get("sq5"), Some(&Value::Int(25)));
+}
+
+#[test]
+fn seekable_writer() {
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.write_all(b"prefix").unwrap();
+    to_writer(&mut cursor, &Squares(6)).unwrap();
+    cursor.seek(SeekFrom::Start(0)).unwrap();
+    let written = cursor.into_inner();
+    assert_eq!(&written[..6], b"prefix");
+    assert_eq!(written[6..], to_bytes(&Squares(6)).unwrap());
+}
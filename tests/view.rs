@@ -0,0 +1,46 @@
+use serde_construct_classic::{to_bytes, ErrorKind, HashTable, TableView, Value};
+
+fn save_file() -> Vec<u8> {
+    let mut table = HashTable::new();
+    for i in 0..100 {
+        table.push(format!("slot{i}"), i);
+    }
+    table.push("player", "Bob");
+    table.push("café", "crème");
+    table.push("time", 12.5);
+    table.push("slot7", -1);
+    to_bytes(&table).unwrap()
+}
+
+#[test]
+fn lookups() {
+    let bytes = save_file();
+    let view = TableView::new(&bytes).unwrap();
+    assert_eq!(view.len(), 104);
+    assert!(view.contains("slot99"));
+    assert!(!view.contains("slot100"));
+    assert_eq!(view.get("slot7"), Some(Value::Int(7)));
+    assert_eq!(view.get("café"), Some(Value::String("crème".to_owned())));
+    assert_eq!(view.get("missing"), None);
+    assert_eq!(view.keys().last().unwrap(), "slot7");
+}
+
+#[test]
+fn typed_lookups() {
+    let bytes = save_file();
+    let view = TableView::new(&bytes).unwrap();
+    assert_eq!(view.get_as::<f32>("time").unwrap(), Some(12.5));
+    assert_eq!(view.get_as::<&str>("player").unwrap(), Some("Bob"));
+    assert_eq!(view.get_as::<String>("café").unwrap().as_deref(), Some("crème"));
+    assert_eq!(view.get_as::<u8>("nothing").unwrap(), None);
+
+    let err = view.get_as::<i64>("player").unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+    assert!(err.offset().unwrap() > 1000);
+}
+
+#[test]
+fn truncated_table() {
+    let bytes = save_file();
+    assert!(matches!(TableView::new(&bytes[..bytes.len() - 3]).err().unwrap().kind(), ErrorKind::UnexpectedEnd));
+}
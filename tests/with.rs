@@ -0,0 +1,106 @@
+use serde_construct_classic::{from_bytes, to_bytes, with, ErrorKind, HashTable, Value};
+use serde_derive::{Deserialize, Serialize};
+
+/// Serializes `value`, checks the table it produces, and reads it back.
+fn round_trip<T>(value: &T, expected: HashTable) -> T
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let bytes = to_bytes(value).unwrap();
+    assert_eq!(from_bytes::<HashTable>(&bytes).unwrap(), expected);
+    from_bytes(&bytes).unwrap()
+}
+
+fn table(entries: &[(&str, Value)]) -> HashTable {
+    entries.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Numbers {
+    #[serde(with = "with::as_float")]
+    volume: u8,
+    #[serde(with = "with::as_int")]
+    speed: f64,
+}
+
+#[test]
+fn numbers_with_forced_tags() {
+    let numbers = Numbers { volume: 80, speed: 250.0 };
+    let expected = table(&[("volume", Value::Float(80.0)), ("speed", Value::Int(250))]);
+    assert_eq!(round_trip(&numbers, expected), numbers);
+}
+
+#[test]
+fn forced_tags_are_checked_when_reading() {
+    let bytes = to_bytes(&table(&[("volume", Value::Int(80)), ("speed", Value::Int(250))])).unwrap();
+    let err = from_bytes::<Numbers>(&bytes).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TypeMismatch));
+}
+
+#[test]
+fn fractional_floats_cannot_be_written_as_ints() {
+    assert!(to_bytes(&Numbers { volume: 80, speed: 2.5 }).is_err());
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Flags {
+    #[serde(with = "with::bool_as_int")]
+    enabled: bool,
+    #[serde(with = "with::bool_as_string")]
+    visible: bool,
+}
+
+#[test]
+fn bools() {
+    let flags = Flags { enabled: true, visible: false };
+    let expected = table(&[("enabled", Value::Int(1)), ("visible", Value::String("false".into()))]);
+    assert_eq!(round_trip(&flags, expected), flags);
+
+    let bytes = to_bytes(&table(&[("enabled", Value::Int(-1)), ("visible", Value::String("TRUE".into()))])).unwrap();
+    assert_eq!(from_bytes::<Flags>(&bytes).unwrap(), Flags { enabled: true, visible: true });
+    let bytes = to_bytes(&table(&[("enabled", Value::Int(0)), ("visible", Value::String("yes".into()))])).unwrap();
+    assert!(from_bytes::<Flags>(&bytes).is_err());
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Colors {
+    #[serde(with = "with::rgb")]
+    background: [u8; 3],
+}
+
+#[test]
+fn rgb() {
+    let colors = Colors { background: [0x12, 0x34, 0x56] };
+    let expected = table(&[("background", Value::Int(0x563412))]);
+    assert_eq!(round_trip(&colors, expected), colors);
+
+    let bytes = to_bytes(&table(&[("background", Value::Int(0x1000000))])).unwrap();
+    assert!(from_bytes::<Colors>(&bytes).is_err());
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Save {
+    #[serde(with = "with::base64_bytes")]
+    thumbnail: Vec<u8>,
+}
+
+#[test]
+fn base64_bytes() {
+    let save = Save { thumbnail: vec![0, 1, 2, 255] };
+    let expected = table(&[("thumbnail", Value::String("AAEC/w==".into()))]);
+    assert_eq!(round_trip(&save, expected), save);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_in_string() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Inventory {
+        #[serde(with = "with::json_in_string")]
+        items: Vec<String>,
+    }
+
+    let inventory = Inventory { items: vec!["sword".into(), "shield".into()] };
+    let expected = table(&[("items", Value::String(r#"["sword","shield"]"#.into()))]);
+    assert_eq!(round_trip(&inventory, expected), inventory);
+}